@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::RangeInclusive};
 
 use crate::util::{
     position::{Direction, Position},
@@ -7,106 +7,243 @@ use crate::util::{
 
 type Height = u8;
 
-struct Hiker<'a> {
-    map: &'a Vec2D<Height>,
-    pos: Position,
-    height: Height,
+/// which neighbours a hiker may step to
+#[derive(Debug, Clone)]
+pub struct StepRule {
+    /// height differences allowed for a single step, all of the same sign
+    pub climb: RangeInclusive<i8>,
+    pub diagonal: bool,
 }
 
-enum HikeStep<'a> {
-    ReachedTop(Position),
-    Split(Vec<Hiker<'a>>),
+impl Default for StepRule {
+    fn default() -> Self {
+        StepRule {
+            climb: 1..=1,
+            diagonal: false,
+        }
+    }
 }
 
-impl<'a> Hiker<'a> {
-    fn hike(self) -> HikeStep<'a> {
-        if *self.map.get(self.pos).unwrap() == 9 {
-            return HikeStep::ReachedTop(self.pos);
+impl StepRule {
+    /// height of trailheads and peaks
+    fn ends(&self) -> (Height, Height) {
+        assert!(
+            *self.climb.start() > 0 || *self.climb.end() < 0,
+            "every step has to change the height in the same direction"
+        );
+        if *self.climb.start() > 0 {
+            (0, 9)
+        } else {
+            (9, 0)
         }
-        let next_height = self.height + 1;
-        let paths = Direction::all()
-            .flat_map(|dir| self.pos.next(dir))
-            .filter(|&pos| match self.map.get(pos) {
-                None => false,
-                Some(&heigh) => heigh == next_height,
-            })
-            .map(|pos| Hiker {
-                map: self.map,
-                pos,
-                height: next_height,
-            })
-            .collect();
-        HikeStep::Split(paths)
+    }
+
+    /// largest height difference of a single step
+    fn span(&self) -> usize {
+        self.climb
+            .start()
+            .unsigned_abs()
+            .max(self.climb.end().unsigned_abs()) as usize
+    }
+
+    fn allows(&self, from: Height, to: Height) -> bool {
+        self.climb.contains(&(to as i8 - from as i8))
     }
 }
 
-struct Map {
+pub struct Map {
     map: Vec2D<Height>,
-    starts: Vec<Position>,
 }
 
-struct HikeResult {
-    reached_tops: usize,
-    unique_ways: usize,
+#[derive(Debug, PartialEq, Eq)]
+pub struct HikeResult {
+    pub start: Position,
+    pub reached_tops: usize,
+    pub unique_ways: usize,
 }
 
 impl Map {
-    fn parse(input: &str) -> Self {
-        let mut starts = Vec::new();
+    pub fn parse(input: &str) -> Self {
         let mut map = Vec::new();
-        for (idx_row, line) in input.lines().enumerate() {
+        for line in input.lines() {
             let mut row = Vec::new();
-            for (idx_col, c) in line.chars().enumerate() {
-                let height = c.to_digit(10).unwrap() as Height;
-                row.push(height);
-                if height == 0 {
-                    starts.push(Position(idx_row, idx_col));
-                }
+            for c in line.chars() {
+                row.push(c.to_digit(10).unwrap() as Height);
             }
             map.push(row);
         }
-        Map {
-            map: Vec2D(map),
-            starts,
+        Map { map: Vec2D(map) }
+    }
+
+    fn height(&self, pos: Position) -> Height {
+        *self.map.get(pos).unwrap()
+    }
+
+    fn neighbours(&self, pos: Position, rule: &StepRule) -> Vec<Position> {
+        use Direction::*;
+        let mut neighbours: Vec<Option<Position>> =
+            Direction::all().map(|dir| pos.next(dir)).collect();
+        if rule.diagonal {
+            for (vertical, horizontal) in [(Up, Left), (Up, Right), (Down, Left), (Down, Right)] {
+                neighbours.push(pos.next(vertical).and_then(|pos| pos.next(horizontal)));
+            }
         }
+        let from = self.height(pos);
+        neighbours
+            .into_iter()
+            .flatten()
+            .filter(|&next| self.map.get(next).is_some_and(|&to| rule.allows(from, to)))
+            .collect()
     }
 
-    fn hike(&self, start: Position) -> HikeResult {
-        let mut reached_top = HashSet::new();
-        let mut unique_ways = 0;
-        let hiker = Hiker {
-            map: &self.map,
-            pos: start,
-            height: 0,
+    /// scores and ratings of all trailheads, each height is a layer whose steps only lead
+    /// to layers closer to the peaks, so every plot is visited once
+    ///
+    /// the peak sets of a layer are dropped once no step can reach it anymore, so at most the
+    /// layers one step spans hold a set of `peaks / 64` words per plot
+    pub fn hike(&self, rule: &StepRule) -> Vec<HikeResult> {
+        let (start, peak) = rule.ends();
+        let cols = self.map.0[0].len();
+        let idx = |pos: Position| pos.0 * cols + pos.1;
+        let mut layers = vec![Vec::new(); 10];
+        for pos in self.map.iter_pos() {
+            layers[self.height(pos) as usize].push(pos);
+        }
+        // reachable peaks as bit set and number of trails per plot
+        let words = layers[peak as usize].len().div_ceil(64);
+        let mut reached: Vec<Vec<u64>> = vec![Vec::new(); self.map.0.len() * cols];
+        let mut ways = vec![0; reached.len()];
+        for (bit, &pos) in layers[peak as usize].iter().enumerate() {
+            reached[idx(pos)] = vec![0; words];
+            reached[idx(pos)][bit / 64] |= 1 << (bit % 64);
+            ways[idx(pos)] = 1;
+        }
+        let heights: Vec<Height> = if start < peak {
+            (start..peak).rev().collect()
+        } else {
+            (peak + 1..=start).collect()
         };
-        let mut paths = vec![hiker];
-        while let Some(path) = paths.pop() {
-            use HikeStep::*;
-            match path.hike() {
-                ReachedTop(top) => {
-                    reached_top.insert(top);
-                    unique_ways += 1;
-                }
-                Split(new_paths) => {
-                    for new_path in new_paths.into_iter() {
-                        paths.push(new_path);
+        let span = rule.span();
+        for height in heights {
+            for &pos in layers[height as usize].iter() {
+                let mut peaks = vec![0_u64; words];
+                for next in self.neighbours(pos, rule) {
+                    ways[idx(pos)] += ways[idx(next)];
+                    for (word, next_word) in peaks.iter_mut().zip(reached[idx(next)].iter()) {
+                        *word |= next_word;
                     }
                 }
+                reached[idx(pos)] = peaks;
             }
+            let unreachable = if start < peak {
+                height as usize + span
+            } else {
+                (height as usize).wrapping_sub(span)
+            };
+            for &pos in layers.get(unreachable).into_iter().flatten() {
+                reached[idx(pos)] = Vec::new();
+            }
+        }
+        layers[start as usize]
+            .iter()
+            .map(|&pos| HikeResult {
+                start: pos,
+                reached_tops: reached[idx(pos)]
+                    .iter()
+                    .map(|word| word.count_ones() as usize)
+                    .sum(),
+                unique_ways: ways[idx(pos)],
+            })
+            .collect()
+    }
+
+    /// peaks reachable from the plot
+    pub fn peaks(&self, start: Position, rule: &StepRule) -> HashSet<Position> {
+        let (_, peak) = rule.ends();
+        let mut visited = HashSet::from([start]);
+        let mut positions = vec![start];
+        let mut peaks = HashSet::new();
+        while let Some(pos) = positions.pop() {
+            if self.height(pos) == peak {
+                peaks.insert(pos);
+                continue;
+            }
+            for next in self.neighbours(pos, rule) {
+                if visited.insert(next) {
+                    positions.push(next);
+                }
+            }
+        }
+        peaks
+    }
+
+    /// at most `cap` trails from the plot to a peak, found lazily
+    pub fn trails<'a>(&'a self, start: Position, rule: &'a StepRule, cap: usize) -> Trails<'a> {
+        Trails {
+            map: self,
+            rule,
+            peak: rule.ends().1,
+            paths: vec![vec![start]],
+            remaining: cap,
+        }
+    }
+
+    /// the heights along the trail, every other plot as `.`
+    pub fn render(&self, trail: &[Position]) -> String {
+        let trail: HashSet<&Position> = trail.iter().collect();
+        let mut rendered = String::new();
+        for (idx_row, row) in self.map.0.iter().enumerate() {
+            for (idx_col, height) in row.iter().enumerate() {
+                if trail.contains(&Position(idx_row, idx_col)) {
+                    rendered.push(char::from_digit(*height as u32, 10).unwrap());
+                } else {
+                    rendered.push('.');
+                }
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+}
+
+/// depth first walk over all trails of a trailhead
+pub struct Trails<'a> {
+    map: &'a Map,
+    rule: &'a StepRule,
+    peak: Height,
+    paths: Vec<Vec<Position>>,
+    remaining: usize,
+}
+
+impl Iterator for Trails<'_> {
+    type Item = Vec<Position>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
-        HikeResult {
-            reached_tops: reached_top.len(),
-            unique_ways,
+        while let Some(path) = self.paths.pop() {
+            let pos = *path.last().unwrap();
+            if self.map.height(pos) == self.peak {
+                self.remaining -= 1;
+                return Some(path);
+            }
+            // reversed so trails come out in direction order
+            for next in self.map.neighbours(pos, self.rule).into_iter().rev() {
+                let mut path = path.clone();
+                path.push(next);
+                self.paths.push(path);
+            }
         }
+        None
     }
 }
 
 pub fn part_one(input: &str) -> usize {
     let map = Map::parse(input);
     let mut sum = 0;
-    for start in map.starts.iter() {
-        let score = map.hike(*start).reached_tops;
-        sum += score;
+    for result in map.hike(&StepRule::default()) {
+        sum += result.reached_tops;
     }
     sum
 }
@@ -114,9 +251,8 @@ pub fn part_one(input: &str) -> usize {
 pub fn part_two(input: &str) -> usize {
     let map = Map::parse(input);
     let mut sum = 0;
-    for start in map.starts.iter() {
-        let rating = map.hike(*start).unique_ways;
-        sum += rating;
+    for result in map.hike(&StepRule::default()) {
+        sum += result.unique_ways;
     }
     sum
 }
@@ -136,11 +272,121 @@ mod tests {
 32019012
 01329801
 10456732";
-        assert_eq!(5, {
-            let map = Map::parse(input);
-            map.hike(map.starts[0]).reached_tops
-        });
+        assert_eq!(
+            5,
+            Map::parse(input).hike(&StepRule::default())[0].reached_tops
+        );
         assert_eq!(36, part_one(input));
         assert_eq!(81, part_two(input));
     }
+
+    /// every trail walked on its own
+    fn brute_force(map: &Map, rule: &StepRule) -> Vec<HikeResult> {
+        let (start, peak) = rule.ends();
+        let mut results = Vec::new();
+        for pos in map.map.iter_pos().filter(|&pos| map.height(pos) == start) {
+            let mut tops = HashSet::new();
+            let mut ways = 0;
+            let mut hikers = vec![pos];
+            while let Some(hiker) = hikers.pop() {
+                if map.height(hiker) == peak {
+                    tops.insert(hiker);
+                    ways += 1;
+                } else {
+                    hikers.extend(map.neighbours(hiker, rule));
+                }
+            }
+            results.push(HikeResult {
+                start: pos,
+                reached_tops: tops.len(),
+                unique_ways: ways,
+            });
+        }
+        results
+    }
+
+    #[test]
+    fn test_step_rules() {
+        let map = Map::parse(
+            "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732",
+        );
+        let rules = [
+            StepRule::default(),
+            StepRule {
+                climb: 1..=1,
+                diagonal: true,
+            },
+            StepRule {
+                climb: 1..=3,
+                diagonal: false,
+            },
+            StepRule {
+                climb: -2..=-1,
+                diagonal: true,
+            },
+        ];
+        for rule in rules.iter() {
+            assert_eq!(brute_force(&map, rule), map.hike(rule));
+        }
+        let descending = StepRule {
+            climb: -1..=-1,
+            diagonal: false,
+        };
+        let ways = |rule| {
+            map.hike(rule)
+                .iter()
+                .map(|result| result.unique_ways)
+                .sum::<usize>()
+        };
+        assert_eq!(81, ways(&descending));
+    }
+
+    #[test]
+    fn test_trails() {
+        let map = Map::parse(
+            "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732",
+        );
+        let rule = StepRule::default();
+        let start = Position(0, 2);
+        assert_eq!(
+            HashSet::from([
+                Position(0, 1),
+                Position(3, 0),
+                Position(3, 4),
+                Position(4, 5),
+                Position(5, 4)
+            ]),
+            map.peaks(start, &rule)
+        );
+        assert_eq!(20, map.trails(start, &rule, usize::MAX).count());
+        assert_eq!(3, map.trails(start, &rule, 3).count());
+        let trail = map.trails(start, &rule, 1).next().unwrap();
+        assert_eq!(10, trail.len());
+        assert_eq!(
+            ".901....
+.8.2....
+.743....
+.65.....
+........
+........
+........
+........
+",
+            map.render(&trail)
+        );
+    }
 }
@@ -1,77 +1,459 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
-struct Stone(usize);
+use crate::util::dot::Dot;
 
-type Cache = HashMap<(Stone, usize), usize>;
+/// the rules of the puzzle, one `<predicate> => <action>` per line, the first matching rule applies
+pub const PUZZLE_RULES: &str = "base 10
+value 0 => replace 1
+even digits => split
+any => multiply 2024";
 
-impl Stone {
-    fn blink(mut self) -> Vec<Stone> {
-        if self.0 == 0 {
-            self.0 = 1;
-            return vec![self];
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+struct Stone(u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    Value(u64),
+    EvenDigits,
+    Any,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Replace(u64),
+    /// left and right half of the digits
+    Split,
+    Multiply(u64),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rules {
+    base: u64,
+    rules: Vec<(Predicate, Action)>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::parse(PUZZLE_RULES)
+    }
+}
+
+impl Rules {
+    pub fn parse(input: &str) -> Self {
+        let mut base = 10;
+        let mut rules = Vec::new();
+        for line in input.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(number) = line.strip_prefix("base ") {
+                base = number.parse().unwrap();
+                assert!(base >= 2, "base {} has no digits", base);
+                continue;
+            }
+            let (predicate, action) = line.split_once(" => ").unwrap();
+            let predicate = match predicate.split_once(' ') {
+                Some(("value", number)) => Predicate::Value(number.parse().unwrap()),
+                None if predicate == "any" => Predicate::Any,
+                _ if predicate == "even digits" => Predicate::EvenDigits,
+                _ => panic!("unknown predicate {}", predicate),
+            };
+            let action = match action.split_once(' ') {
+                Some(("replace", number)) => Action::Replace(number.parse().unwrap()),
+                Some(("multiply", number)) => Action::Multiply(number.parse().unwrap()),
+                None if action == "split" => Action::Split,
+                _ => panic!("unknown action {}", action),
+            };
+            rules.push((predicate, action));
         }
-        let mut number = self.0.to_string();
-        let digit_cnt = number.len();
-        if number.len() % 2 == 0 {
-            let right = number.split_off(digit_cnt / 2);
-            self.0 = number.parse().unwrap();
-            return vec![self, Stone(right.parse().unwrap())];
+        Rules { base, rules }
+    }
+
+    fn digit_cnt(&self, mut value: u64) -> u32 {
+        let mut digit_cnt = 1;
+        while value >= self.base {
+            value /= self.base;
+            digit_cnt += 1;
         }
-        self.0 *= 2024;
-        vec![self]
+        digit_cnt
     }
 
-    fn count(self, blinks: usize, cache: &mut Cache) -> usize {
-        if blinks == 0 {
-            return 1;
+    /// `None` if the new value does not fit into a `u64`, an unmatched stone stays as it is
+    fn blink(&self, stone: Stone) -> Option<(Stone, Option<Stone>)> {
+        let digit_cnt = self.digit_cnt(stone.0);
+        let rule = self.rules.iter().find(|(predicate, _)| match predicate {
+            Predicate::Value(value) => stone.0 == *value,
+            Predicate::EvenDigits => digit_cnt.is_multiple_of(2),
+            Predicate::Any => true,
+        });
+        let blinked = match rule {
+            None => (stone, None),
+            Some((_, Action::Replace(value))) => (Stone(*value), None),
+            Some((_, Action::Split)) => {
+                let half = self.base.pow(digit_cnt / 2);
+                (Stone(stone.0 / half), Some(Stone(stone.0 % half)))
+            }
+            Some((_, Action::Multiply(factor))) => (Stone(stone.0.checked_mul(*factor)?), None),
+        };
+        Some(blinked)
+    }
+}
+
+/// the stone count or an engraved value no longer fits during this blink
+#[derive(Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub blink: usize,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stones overflow at blink {}", self.blink)
+    }
+}
+
+/// modulus for counting which is not prime, the recurrence needs the inverse of every residue
+#[derive(Debug, PartialEq, Eq)]
+pub struct NotPrime(pub u32);
+
+impl Display for NotPrime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "modulus {} is not prime", self.0)
+    }
+}
+
+fn is_prime(n: u32) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n as u64)
+            .all(|d| !(n as u64).is_multiple_of(d))
+}
+
+/// number of stones per engraved value, the order of the line never matters
+pub struct StoneLine {
+    stones: HashMap<Stone, u128>,
+}
+
+impl StoneLine {
+    pub fn parse(input: &str) -> Self {
+        let mut stones = HashMap::new();
+        for number in input.split_whitespace() {
+            *stones.entry(Stone(number.parse().unwrap())).or_insert(0) += 1;
         }
-        if let Some(count) = cache.get(&(self, blinks)) {
-            return *count;
+        Self { stones }
+    }
+
+    pub fn blink(&self, rules: &Rules, cnt: usize) -> Result<u128, Overflow> {
+        let total = |stones: &HashMap<Stone, u128>, blink| {
+            stones
+                .values()
+                .try_fold(0_u128, |sum, &count| sum.checked_add(count))
+                .ok_or(Overflow { blink })
+        };
+        let mut stones = self.stones.clone();
+        let mut sum = total(&stones, 0)?;
+        for blink in 1..=cnt {
+            let mut next = HashMap::with_capacity(stones.len());
+            for (stone, count) in stones.into_iter() {
+                let (left, right) = rules.blink(stone).ok_or(Overflow { blink })?;
+                for stone in [Some(left), right].into_iter().flatten() {
+                    let entry = next.entry(stone).or_insert(0_u128);
+                    *entry = entry.checked_add(count).ok_or(Overflow { blink })?;
+                }
+            }
+            sum = total(&next, blink)?;
+            stones = next;
+        }
+        Ok(sum)
+    }
+
+    /// fails if a reachable value does not fit into a `u64`, `blink` is its distance to the line
+    pub fn transition_graph(&self, rules: &Rules) -> Result<TransitionGraph, Overflow> {
+        let mut initial: Vec<(&Stone, &u128)> = self.stones.iter().collect();
+        initial.sort_by_key(|(stone, _)| stone.0);
+        let mut ids = HashMap::new();
+        let mut graph = TransitionGraph {
+            values: Vec::new(),
+            edges: Vec::new(),
+            counts: Vec::new(),
+        };
+        let mut depths = Vec::new();
+        for (&stone, &count) in initial {
+            ids.insert(stone, graph.values.len());
+            graph.values.push(stone.0);
+            graph.counts.push(count);
+            depths.push(0);
         }
-        let mut sum = 0;
-        for stone in self.blink() {
-            sum += stone.count(blinks - 1, cache);
+        let mut node = 0;
+        while node < graph.values.len() {
+            let blink = depths[node] + 1;
+            let (left, right) = rules
+                .blink(Stone(graph.values[node]))
+                .ok_or(Overflow { blink })?;
+            let mut edges = Vec::new();
+            for stone in [Some(left), right].into_iter().flatten() {
+                let id = *ids.entry(stone).or_insert_with(|| {
+                    graph.values.push(stone.0);
+                    graph.counts.push(0);
+                    depths.push(blink);
+                    graph.values.len() - 1
+                });
+                edges.push(id);
+            }
+            graph.edges.push(edges);
+            node += 1;
         }
-        cache.insert((self, blinks), sum);
-        sum
+        Ok(graph)
     }
 }
 
-struct StoneLine {
-    stones: Vec<Stone>,
-    cache: Cache,
+/// every value reachable from a stone line, with one edge per stone a value turns into
+pub struct TransitionGraph {
+    values: Vec<u64>,
+    edges: Vec<Vec<usize>>,
+    /// stones per value in the initial line
+    counts: Vec<u128>,
 }
 
-impl StoneLine {
-    fn parse(input: &str) -> Self {
-        let mut stones = Vec::new();
-        for number in input.split_whitespace() {
-            stones.push(Stone(number.parse().unwrap()));
+struct Tarjan<'a> {
+    edges: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, node: usize) {
+        let index = self.next_index;
+        self.next_index += 1;
+        self.index[node] = Some(index);
+        self.low[node] = index;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+        for &next in self.edges[node].iter() {
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.low[node] = self.low[node].min(self.low[next]);
+                }
+                Some(next_index) if self.on_stack[next] => {
+                    self.low[node] = self.low[node].min(next_index);
+                }
+                Some(_) => {}
+            }
+        }
+        if self.low[node] == index {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
         }
-        Self {
-            stones,
-            cache: HashMap::new(),
+    }
+}
+
+impl TransitionGraph {
+    pub fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    /// strongly connected components as sorted values, successors come before their predecessors
+    pub fn components(&self) -> Vec<Vec<u64>> {
+        let nodes = self.values.len();
+        let mut tarjan = Tarjan {
+            edges: &self.edges,
+            index: vec![None; nodes],
+            low: vec![0; nodes],
+            stack: Vec::new(),
+            on_stack: vec![false; nodes],
+            next_index: 0,
+            components: Vec::new(),
+        };
+        for node in 0..nodes {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
         }
+        tarjan
+            .components
+            .into_iter()
+            .map(|component| {
+                let mut values: Vec<u64> = component.into_iter().map(|n| self.values[n]).collect();
+                values.sort();
+                values
+            })
+            .collect()
     }
 
-    fn blink(&mut self, cnt: usize) -> usize {
-        let mut sum = 0;
-        for stone in self.stones.iter() {
-            sum += stone.count(cnt, &mut self.cache);
+    /// initial stones are boxes
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::digraph();
+        for (node, value) in self.values.iter().enumerate() {
+            let shape = if self.counts[node] > 0 {
+                "box"
+            } else {
+                "ellipse"
+            };
+            dot.node(&value.to_string(), &[("shape", shape)]);
+            for &next in self.edges[node].iter() {
+                dot.edge(&value.to_string(), &self.values[next].to_string(), &[]);
+            }
         }
-        sum
+        dot.to_string()
     }
+
+    /// stone counts modulo `prime` for the first `len` blinks
+    fn counts_mod(&self, len: usize, prime: u32) -> Vec<u64> {
+        let prime = prime as u64;
+        let mut counts: Vec<u64> = self
+            .counts
+            .iter()
+            .map(|&count| (count % prime as u128) as u64)
+            .collect();
+        let mut sequence = Vec::with_capacity(len);
+        for _ in 0..len {
+            sequence.push(counts.iter().sum::<u64>() % prime);
+            let mut next = vec![0; counts.len()];
+            for (node, &count) in counts.iter().enumerate() {
+                for &target in self.edges[node].iter() {
+                    next[target] = (next[target] + count) % prime;
+                }
+            }
+            counts = next;
+        }
+        sequence
+    }
+
+    /// stone count after `blinks` modulo `prime`
+    ///
+    /// raising the transition matrix to the n-th power is too slow with thousands of values,
+    /// instead the count sequence is reduced to its minimal recurrence (Berlekamp–Massey),
+    /// which is then raised to the n-th power as polynomial modulo its characteristic polynomial
+    pub fn count_mod(&self, blinks: u64, prime: u32) -> Result<u64, NotPrime> {
+        if !is_prime(prime) {
+            return Err(NotPrime(prime));
+        }
+        // the recurrence has at most one term per value, twice as many terms determine it
+        let sequence = self.counts_mod(2 * self.size() + 1, prime);
+        if let Some(&count) = sequence.get(blinks as usize) {
+            return Ok(count);
+        }
+        let recurrence = berlekamp_massey(&sequence, prime as u64);
+        Ok(nth_term(&sequence, &recurrence, blinks, prime as u64))
+    }
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, prime: u64) -> u64 {
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % prime;
+        }
+        base = base * base % prime;
+        exp >>= 1;
+    }
+    result
+}
+
+/// coefficients `r` of the shortest recurrence `a[n] = r[0] * a[n - 1] + r[1] * a[n - 2] + ...`
+fn berlekamp_massey(sequence: &[u64], prime: u64) -> Vec<u64> {
+    let mut current = vec![1];
+    let mut previous = vec![1];
+    let mut len = 0;
+    let mut shift = 1;
+    let mut last_discrepancy = 1;
+    for n in 0..sequence.len() {
+        let mut discrepancy = sequence[n];
+        for i in 1..=len {
+            discrepancy = (discrepancy + current[i] * sequence[n - i]) % prime;
+        }
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let coef = discrepancy * pow_mod(last_discrepancy, prime - 2, prime) % prime;
+        let before = current.clone();
+        if current.len() < previous.len() + shift {
+            current.resize(previous.len() + shift, 0);
+        }
+        for (i, &p) in previous.iter().enumerate() {
+            current[i + shift] = (current[i + shift] + prime - coef * p % prime) % prime;
+        }
+        if 2 * len <= n {
+            len = n + 1 - len;
+            previous = before;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+    current.resize(len + 1, 0);
+    current[1..].iter().map(|&c| (prime - c) % prime).collect()
+}
+
+/// `a[n]` as combination of the first terms, with `x^n` modulo the characteristic polynomial
+fn nth_term(sequence: &[u64], recurrence: &[u64], n: u64, prime: u64) -> u64 {
+    let order = recurrence.len();
+    if order == 0 {
+        return 0;
+    }
+    // prime < 2^32 so a product fits into u64 and a sum of products into u128
+    let mul = |a: &[u64], b: &[u64]| -> Vec<u64> {
+        let mut product = vec![0_u128; 2 * order - 1];
+        for (i, &a) in a.iter().enumerate() {
+            for (j, &b) in b.iter().enumerate() {
+                product[i + j] += (a * b) as u128;
+            }
+        }
+        // x^i = r[0] * x^(i - 1) + r[1] * x^(i - 2) + ...
+        for i in (order..product.len()).rev() {
+            let coef = (product[i] % prime as u128) as u64;
+            for (j, &r) in recurrence.iter().enumerate() {
+                product[i - 1 - j] += (coef * r) as u128;
+            }
+        }
+        product[..order]
+            .iter()
+            .map(|&c| (c % prime as u128) as u64)
+            .collect()
+    };
+    let mut result = vec![0; order];
+    result[0] = 1;
+    let mut base = vec![0; order];
+    if order == 1 {
+        base[0] = recurrence[0];
+    } else {
+        base[1] = 1;
+    }
+    let mut exp = n;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul(&result, &base);
+        }
+        base = mul(&base, &base);
+        exp >>= 1;
+    }
+    result
+        .iter()
+        .zip(sequence)
+        .fold(0, |sum, (&r, &a)| (sum + r * a) % prime)
 }
 
-pub fn part_one(input: &str) -> usize {
-    let mut stone_line = StoneLine::parse(input);
-    stone_line.blink(25)
+pub fn part_one(input: &str) -> u128 {
+    let stone_line = StoneLine::parse(input);
+    stone_line.blink(&Rules::default(), 25).unwrap()
 }
 
-pub fn part_two(input: &str) -> usize {
-    let mut stone_line = StoneLine::parse(input);
-    stone_line.blink(75)
+pub fn part_two(input: &str) -> u128 {
+    let stone_line = StoneLine::parse(input);
+    stone_line.blink(&Rules::default(), 75).unwrap()
 }
 
 #[cfg(test)]
@@ -82,6 +464,91 @@ mod tests {
     #[test]
     fn test_example() {
         let input = "125 17";
+        assert_eq!(Ok(22), StoneLine::parse(input).blink(&Rules::default(), 6));
         assert_eq!(55312, part_one(input));
     }
+
+    #[test]
+    fn test_split() {
+        let rules = Rules::default();
+        assert_eq!(Some((Stone(10), Some(Stone(0)))), rules.blink(Stone(1000)));
+        assert_eq!(Some((Stone(2024), None)), rules.blink(Stone(1)));
+        assert_eq!(Some((Stone(1), None)), rules.blink(Stone(0)));
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules = Rules::parse(
+            "# binary stones
+            base 2
+            even digits => split
+            any => multiply 3",
+        );
+        assert_eq!(
+            vec![
+                (Predicate::EvenDigits, Action::Split),
+                (Predicate::Any, Action::Multiply(3))
+            ],
+            rules.rules
+        );
+        // 1 -> 11 -> 1 1 -> 11 11 -> 1 1 1 1
+        assert_eq!(Ok(4), StoneLine::parse("1").blink(&rules, 4));
+        let rules = Rules::parse("value 7 => replace 8");
+        assert_eq!(Some((Stone(9), None)), rules.blink(Stone(9)));
+    }
+
+    #[test]
+    fn test_transition_graph() {
+        let rules = Rules::default();
+        let graph = StoneLine::parse("0").transition_graph(&rules).unwrap();
+        assert_eq!(54, graph.size());
+        assert_eq!(1, graph.components().len());
+        let line = StoneLine::parse("125 17");
+        let graph = line.transition_graph(&rules).unwrap();
+        assert_eq!(76, graph.size());
+        let components = graph.components();
+        assert_eq!(23, components.len());
+        assert!(components.iter().all(|c| c.len() == 1 || c.len() == 54));
+        assert!(graph.to_dot().contains("\"125\" -> \"253000\""));
+        let rules = Rules::parse("any => multiply 3");
+        assert_eq!(
+            Some(Overflow { blink: 36 }),
+            line.transition_graph(&rules).err()
+        );
+    }
+
+    #[test]
+    fn test_count_mod() {
+        let prime = 1_000_000_007;
+        let line = StoneLine::parse("125 17");
+        let rules = Rules::default();
+        let graph = line.transition_graph(&rules).unwrap();
+        for blinks in [0, 6, 25, 75] {
+            let count = line.blink(&rules, blinks).unwrap();
+            assert_eq!(
+                (count % prime as u128) as u64,
+                graph.count_mod(blinks as u64, prime).unwrap()
+            );
+        }
+        // past the directly simulated terms
+        assert_eq!(Ok(970983047), graph.count_mod(500, prime));
+        assert!(graph.count_mod(1_000_000_000_000, prime).unwrap() < prime as u64);
+        for modulus in [0, 1, 4, 1_000_000_008] {
+            assert_eq!(Err(NotPrime(modulus)), graph.count_mod(500, modulus));
+        }
+        assert!(graph.count_mod(500, 2).is_ok());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let stone_line = StoneLine::parse("125 17");
+        let rules = Rules::default();
+        let Err(Overflow { blink }) = stone_line.blink(&rules, 1000) else {
+            panic!("no overflow");
+        };
+        assert!(blink < 1000);
+        assert!(stone_line.blink(&rules, blink - 1).is_ok());
+        let rules = Rules::parse("any => multiply 1000000");
+        assert_eq!(Err(Overflow { blink: 3 }), stone_line.blink(&rules, 5));
+    }
 }
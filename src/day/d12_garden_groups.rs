@@ -1,7 +1,12 @@
-use std::fmt::{Display, Write};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+};
 
 use crate::util::{
+    dot::Dot,
     position::{Direction, Position},
+    union_find::UnionFind,
     vec2d::Vec2D,
 };
 
@@ -17,13 +22,13 @@ impl Display for Field {
     }
 }
 
-struct Fields {
+pub struct Fields {
     fields: Vec2D<Field>,
     id_gen: usize,
 }
 
 impl Fields {
-    fn parse(input: &str) -> Self {
+    pub fn parse(input: &str) -> Self {
         let mut rows = Vec::new();
         for line in input.lines() {
             let mut row = Vec::new();
@@ -35,10 +40,12 @@ impl Fields {
             }
             rows.push(row);
         }
-        Fields {
+        let mut fields = Fields {
             fields: Vec2D(rows),
             id_gen: 0,
-        }
+        };
+        fields.group();
+        fields
     }
 
     fn find_neighbour(&mut self, start: Position) {
@@ -79,120 +86,479 @@ impl Fields {
         }
     }
 
-    fn price_per_fence(&self) -> usize {
-        let mut groups = vec![(0_usize, 0_usize); self.id_gen];
+    /// area, perimeter and sides of all regions in a single pass over the fields
+    pub fn regions(&self) -> Vec<Region> {
+        use Direction::*;
+        let mut regions: Vec<Region> = (0..self.id_gen)
+            .map(|id| Region {
+                id,
+                plant: ' ',
+                cells: Vec::new(),
+                bbox: (Position(usize::MAX, usize::MAX), Position(0, 0)),
+                area: 0,
+                perimeter: 0,
+                sides: 0,
+            })
+            .collect();
         for pos in self.fields.iter_pos() {
             let field = self.fields.get(pos).unwrap();
             let id = field.group.unwrap();
-            let (area, fence) = &mut groups[id];
-            *area += 1;
-            let fences = 4 - Direction::all()
-                .flat_map(|dir| pos.next(dir))
-                .flat_map(|pos| self.fields.get(pos))
-                .filter(|field| field.group.unwrap() == id)
+            let is_in_group = |pos: Option<Position>| {
+                pos.and_then(|pos| self.fields.get(pos))
+                    .is_some_and(|field| field.group == Some(id))
+            };
+            let region = &mut regions[id];
+            region.plant = field.typ;
+            region.cells.push(pos);
+            let (min, max) = &mut region.bbox;
+            *min = Position(min.0.min(pos.0), min.1.min(pos.1));
+            *max = Position(max.0.max(pos.0), max.1.max(pos.1));
+            region.area += 1;
+            region.perimeter += Direction::all()
+                .filter(|&dir| !is_in_group(pos.next(dir)))
                 .count();
-            *fence += fences;
-        }
-        let mut sum = 0;
-        for (area, fence) in groups.into_iter() {
-            sum += area * fence;
+            // a region has as many sides as corners, check each corner of the plot
+            for (vertical, horizontal) in [(Up, Left), (Up, Right), (Down, Left), (Down, Right)] {
+                let vertical_in = is_in_group(pos.next(vertical));
+                let horizontal_in = is_in_group(pos.next(horizontal));
+                let diagonal_in =
+                    is_in_group(pos.next(vertical).and_then(|pos| pos.next(horizontal)));
+                let convex = !vertical_in && !horizontal_in;
+                let concave = vertical_in && horizontal_in && !diagonal_in;
+                if convex || concave {
+                    region.sides += 1;
+                }
+            }
         }
-        sum
+        regions
     }
 
-    fn price_per_side(&self) -> usize {
-        use Direction::*;
-        let mut groups = vec![(0_usize, 0_usize); self.id_gen];
-        for (group_id, (area, sides)) in groups.iter_mut().enumerate() {
-            let is_in_group = |pos: Option<Position>| {
-                pos.and_then(|pos| self.fields.get(pos))
-                .filter(|field| field.group.unwrap() == group_id).is_some()
-            };
-            // iterate over column and rows to find continuous fences
-            for (idx_row, row) in self.fields.0.iter().enumerate() {
-                let mut sides_row = 0;
-                let mut side_top = 0;
-                let mut side_bot = 0;
-                for (idx_col, field) in row.iter().enumerate() {
-                    if field.group.unwrap() == group_id {
-                        *area += 1;
-                        let pos = Position(idx_row, idx_col);
-                        let pos_top = pos.next(Up);
-                        if is_in_group(pos_top) {
-                            sides_row += side_top;
-                            side_top = 0;
-                        } else {
-                            side_top = 1;
-                        }
-                        let pos_bot = pos.next(Down);
-                        if is_in_group(pos_bot) {
-                            sides_row += side_bot;
-                            side_bot = 0;
-                        } else {
-                            side_bot = 1;
+    /// neighbouring regions and regions lying inside others, by group id
+    pub fn region_graph(&self) -> RegionGraph {
+        let regions = self.id_gen;
+        let mut plants = vec![' '; regions];
+        let mut fences = BTreeMap::new();
+        // the area around the garden is an extra node
+        let outside = regions;
+        let mut neighbours = vec![Vec::new(); regions + 1];
+        for pos in self.fields.iter_pos() {
+            let field = self.fields.get(pos).unwrap();
+            let id = field.group.unwrap();
+            plants[id] = field.typ;
+            for dir in Direction::all() {
+                match pos.next(dir).and_then(|pos| self.fields.get(pos)) {
+                    Some(next) => {
+                        let next = next.group.unwrap();
+                        // every shared fence is seen from both sides, count it once
+                        if id < next {
+                            *fences.entry((id, next)).or_insert(0) += 1;
                         }
-                    } else {
-                        sides_row += side_top + side_bot;
-                        side_top = 0;
-                        side_bot = 0;
                     }
+                    None if !neighbours[outside].contains(&id) => {
+                        neighbours[outside].push(id);
+                        neighbours[id].push(outside);
+                    }
+                    None => {}
                 }
-                sides_row += side_top + side_bot;
-                *sides += sides_row;
             }
-            let cols = self.fields.0[0].len();
-            let rows = self.fields.0.len();
-            for idx_col in 0..cols {
-                let mut sides_col = 0;
-                let mut side_left = 0;
-                let mut side_right = 0;
-                for idx_row in 0..rows {
-                    let pos = Position(idx_row, idx_col);
-                    let field = self.fields.get(pos).unwrap();
-                    if field.group.unwrap() == group_id {
-                        //area already counted in row iteration
-                        let pos_left = pos.next(Left);
-                        if is_in_group(pos_left) {
-                            sides_col += side_left;
-                            side_left = 0;
-                        } else {
-                            side_left = 1;
-                        }
-                        let pos_right = pos.next(Right);
-                        if is_in_group(pos_right) {
-                            sides_col += side_right;
-                            side_right = 0;
-                        } else {
-                            side_right = 1;
-                        }
-                    } else {
-                        sides_col += side_left + side_right;
-                        side_left = 0;
-                        side_right = 0;
+        }
+        for &(a, b) in fences.keys() {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+        // without a region in the way, everything not reachable from outside lies inside it
+        let mut enclosers = vec![Vec::new(); regions];
+        let mut holes = vec![0; regions];
+        for around in 0..regions {
+            let mut reached = vec![false; regions + 1];
+            reached[around] = true;
+            flood(&neighbours, outside, &mut reached);
+            for start in 0..regions {
+                if !reached[start] {
+                    holes[around] += 1;
+                    for inside in flood(&neighbours, start, &mut reached) {
+                        enclosers[inside].push(around);
                     }
                 }
-                sides_col += side_left + side_right;
-                *sides += sides_col;
             }
         }
-        let mut sum = 0;
-        for (area, sides) in groups.into_iter() {
-            sum += area * sides;
+        // enclosing regions are nested, the innermost one has the most enclosers itself
+        let enclosed = enclosers
+            .iter()
+            .enumerate()
+            .filter_map(|(id, around)| {
+                let innermost = around
+                    .iter()
+                    .max_by_key(|&&around| enclosers[around].len())?;
+                Some((id, *innermost))
+            })
+            .collect();
+        RegionGraph {
+            plants,
+            fences,
+            enclosed,
+            holes,
         }
-        sum
+    }
+
+    fn price_per_fence(&self) -> usize {
+        let regions = self.regions();
+        regions
+            .iter()
+            .map(|region| region.area * region.perimeter)
+            .sum()
+    }
+
+    fn price_per_side(&self) -> usize {
+        let regions = self.regions();
+        regions
+            .iter()
+            .map(|region| region.area * region.sides)
+            .sum()
+    }
+}
+
+/// plots of the same plant connected horizontally or vertically
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub id: usize,
+    pub plant: char,
+    pub cells: Vec<Position>,
+    /// top left and bottom right plot
+    pub bbox: (Position, Position),
+    pub area: usize,
+    pub perimeter: usize,
+    pub sides: usize,
+}
+
+/// marks the nodes reachable from `start` and returns them
+fn flood(neighbours: &[Vec<usize>], start: usize, reached: &mut [bool]) -> Vec<usize> {
+    let mut nodes = vec![start];
+    reached[start] = true;
+    let mut idx = 0;
+    while let Some(&node) = nodes.get(idx) {
+        for &next in neighbours[node].iter() {
+            if !reached[next] {
+                reached[next] = true;
+                nodes.push(next);
+            }
+        }
+        idx += 1;
+    }
+    nodes
+}
+
+/// `c` as JSON string, quotes, backslashes and control characters escaped
+fn json_string(c: char) -> String {
+    match c {
+        '"' => "\"\\\"\"".to_string(),
+        '\\' => "\"\\\\\"".to_string(),
+        c if c.is_control() => format!("\"\\u{:04x}\"", c as u32),
+        c => format!("\"{}\"", c),
+    }
+}
+
+/// regions are connected by shared fences, the garden border does not count as neighbour
+pub struct RegionGraph {
+    /// plant per region id
+    pub plants: Vec<char>,
+    /// fence length between neighbouring regions, smaller id first
+    pub fences: BTreeMap<(usize, usize), usize>,
+    /// enclosed region to the innermost region around it, the garden border counts as outside
+    /// so a region touching it is never enclosed
+    pub enclosed: BTreeMap<usize, usize>,
+    /// separate groups of regions inside each region
+    pub holes: Vec<usize>,
+}
+
+impl RegionGraph {
+    /// fences are solid edges, enclosures dashed edges
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::graph();
+        for (id, plant) in self.plants.iter().enumerate() {
+            dot.node(&id.to_string(), &[("label", &format!("{} {}", plant, id))]);
+        }
+        for (&(a, b), len) in self.fences.iter() {
+            dot.edge(
+                &a.to_string(),
+                &b.to_string(),
+                &[("label", &len.to_string())],
+            );
+        }
+        for (inside, around) in self.enclosed.iter() {
+            dot.edge(
+                &inside.to_string(),
+                &around.to_string(),
+                &[("style", "dashed")],
+            );
+        }
+        dot.to_string()
+    }
+
+    pub fn to_json(&self) -> String {
+        let regions: Vec<String> = self
+            .plants
+            .iter()
+            .enumerate()
+            .map(|(id, plant)| {
+                let enclosed_by = self
+                    .enclosed
+                    .get(&id)
+                    .map_or("null".to_string(), |around| around.to_string());
+                format!(
+                    "{{\"id\":{},\"plant\":{},\"holes\":{},\"enclosed_by\":{}}}",
+                    id,
+                    json_string(*plant),
+                    self.holes[id],
+                    enclosed_by
+                )
+            })
+            .collect();
+        let fences: Vec<String> = self
+            .fences
+            .iter()
+            .map(|(&(a, b), len)| format!("{{\"regions\":[{},{}],\"length\":{}}}", a, b, len))
+            .collect();
+        format!(
+            "{{\"regions\":[{}],\"fences\":[{}]}}",
+            regions.join(","),
+            fences.join(",")
+        )
+    }
+}
+
+/// garden which keeps regions, areas and perimeters up to date while plots are replanted
+pub struct Garden {
+    plants: Vec2D<char>,
+    /// set per plot, the root of a set identifies the region
+    labels: Vec2D<usize>,
+    sets: UnionFind,
+    /// area and perimeter, valid for set roots
+    stats: Vec<(usize, usize)>,
+    price: usize,
+}
+
+impl Display for Garden {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.plants.fmt(f)
+    }
+}
+
+impl Garden {
+    pub fn parse(input: &str) -> Self {
+        let plants = Vec2D(input.lines().map(|line| line.chars().collect()).collect());
+        let cols = plants.0[0].len();
+        let labels = Vec2D(
+            (0..plants.0.len())
+                .map(|row| (row * cols..(row + 1) * cols).collect())
+                .collect(),
+        );
+        let plots = plants.0.len() * cols;
+        let mut garden = Garden {
+            plants,
+            labels,
+            sets: UnionFind::new(plots),
+            stats: vec![(1, 4); plots],
+            price: 4 * plots,
+        };
+        for pos in garden.plants.iter_pos() {
+            for dir in [Direction::Right, Direction::Down] {
+                if let Some(next) = pos.next(dir).filter(|&next| garden.same_plant(pos, next)) {
+                    garden.join(pos, next);
+                }
+            }
+        }
+        garden
+    }
+
+    fn same_plant(&self, a: Position, b: Position) -> bool {
+        self.plants.get(a).is_some() && self.plants.get(a) == self.plants.get(b)
+    }
+
+    fn neighbours(&self, pos: Position) -> Vec<Position> {
+        Direction::all()
+            .flat_map(|dir| pos.next(dir))
+            .filter(|&next| self.same_plant(pos, next))
+            .collect()
+    }
+
+    fn root(&mut self, pos: Position) -> usize {
+        self.sets.find(*self.labels.get(pos).unwrap())
+    }
+
+    fn cost(&self, root: usize) -> usize {
+        let (area, perimeter) = self.stats[root];
+        area * perimeter
+    }
+
+    /// merges the regions of two neighbouring plots, their shared fence disappears on both sides
+    fn join(&mut self, a: Position, b: Position) {
+        let (root_a, root_b) = (self.root(a), self.root(b));
+        self.price -= self.cost(root_a);
+        let (mut area, mut perimeter) = self.stats[root_a];
+        if root_a != root_b {
+            self.price -= self.cost(root_b);
+            area += self.stats[root_b].0;
+            perimeter += self.stats[root_b].1;
+        }
+        let root = self.sets.union(root_a, root_b);
+        self.stats[root] = (area, perimeter - 2);
+        self.price += self.cost(root);
+    }
+
+    /// gives the plots connected to `start` a new region
+    fn reflood(&mut self, start: Position) {
+        let label = self.sets.push();
+        let (mut area, mut perimeter) = (0, 0);
+        let mut positions = vec![start];
+        *self.labels.get_mut(start).unwrap() = label;
+        while let Some(pos) = positions.pop() {
+            let neighbours = self.neighbours(pos);
+            area += 1;
+            perimeter += 4 - neighbours.len();
+            for next in neighbours {
+                let next_label = self.labels.get_mut(next).unwrap();
+                if *next_label != label {
+                    *next_label = label;
+                    positions.push(next);
+                }
+            }
+        }
+        self.stats.push((area, perimeter));
+        self.price += self.cost(label);
+    }
+
+    /// whether the plots of `plant` next to `pos` are connected through the 8 plots around it
+    fn connected_around(&self, pos: Position, plant: char) -> bool {
+        use Direction::*;
+        let ring = [
+            (Some(Up), None),
+            (Some(Up), Some(Right)),
+            (None, Some(Right)),
+            (Some(Down), Some(Right)),
+            (Some(Down), None),
+            (Some(Down), Some(Left)),
+            (None, Some(Left)),
+            (Some(Up), Some(Left)),
+        ];
+        let planted: Vec<bool> = ring
+            .iter()
+            .map(|&(vertical, horizontal)| {
+                let next = match vertical {
+                    Some(dir) => pos.next(dir),
+                    None => Some(pos),
+                };
+                let next = match horizontal {
+                    Some(dir) => next.and_then(|next| next.next(dir)),
+                    None => next,
+                };
+                next.and_then(|next| self.plants.get(next)) == Some(&plant)
+            })
+            .collect();
+        let Some(gap) = planted.iter().position(|&planted| !planted) else {
+            return true;
+        };
+        // runs of planted plots around the ring, only those with an orthogonal neighbour count
+        let mut runs = 0;
+        let mut orthogonal = false;
+        for step in 1..=ring.len() {
+            let idx = (gap + step) % ring.len();
+            if planted[idx] {
+                orthogonal |= idx.is_multiple_of(2);
+            } else {
+                runs += orthogonal as usize;
+                orthogonal = false;
+            }
+        }
+        runs <= 1
+    }
+
+    /// relabels every region with one of its plots once more sets than plots were handed out
+    fn compact(&mut self) {
+        let plots = self.plants.0.len() * self.plants.0[0].len();
+        let mut relabeled = vec![None; self.stats.len()];
+        let mut stats = vec![(0, 0); plots];
+        for (idx, pos) in self.plants.iter_pos().enumerate() {
+            let root = self.root(pos);
+            let label = *relabeled[root].get_or_insert(idx);
+            if label == idx {
+                stats[idx] = self.stats[root];
+            }
+            *self.labels.get_mut(pos).unwrap() = label;
+        }
+        self.sets = UnionFind::new(plots);
+        self.stats = stats;
+    }
+
+    /// replants a plot, the rest of its old region is only reflooded if the neighbours of the
+    /// plot don't connect around it, which costs as much as the size of that region
+    pub fn set(&mut self, pos: Position, plant: char) {
+        let old = *self.plants.get(pos).unwrap();
+        if old == plant {
+            return;
+        }
+        let old_neighbours = self.neighbours(pos);
+        let root = self.root(pos);
+        self.price -= self.cost(root);
+        *self.plants.get_mut(pos).unwrap() = plant;
+        if old_neighbours.len() <= 1 || self.connected_around(pos, old) {
+            let (area, perimeter) = self.stats[root];
+            // fences of the plot are removed, each neighbour gets a fence instead
+            self.stats[root] = (area - 1, perimeter + 2 * old_neighbours.len() - 4);
+            self.price += self.cost(root);
+        } else {
+            self.stats[root] = (0, 0);
+            for next in old_neighbours {
+                if self.root(next) == root {
+                    self.reflood(next);
+                }
+            }
+        }
+        *self.labels.get_mut(pos).unwrap() = self.sets.push();
+        self.stats.push((1, 4));
+        self.price += 4;
+        for next in self.neighbours(pos) {
+            self.join(pos, next);
+        }
+        if self.stats.len() > 2 * self.plants.0.len() * self.plants.0[0].len() {
+            self.compact();
+        }
+    }
+
+    /// price of the garden if the plot was replanted, the garden is left unchanged
+    pub fn price_if(&mut self, pos: Position, plant: char) -> usize {
+        let old = *self.plants.get(pos).unwrap();
+        self.set(pos, plant);
+        let price = self.price;
+        self.set(pos, old);
+        price
+    }
+
+    /// area and perimeter of the region containing the plot
+    pub fn region(&mut self, pos: Position) -> (usize, usize) {
+        let root = self.root(pos);
+        self.stats[root]
+    }
+
+    pub fn same_region(&mut self, a: Position, b: Position) -> bool {
+        let (a, b) = (*self.labels.get(a).unwrap(), *self.labels.get(b).unwrap());
+        self.sets.same(a, b)
+    }
+
+    /// sum of area times perimeter over all regions
+    pub fn price(&self) -> usize {
+        self.price
     }
 }
 
 pub fn part_one(input: &str) -> usize {
-    let mut fields = Fields::parse(input);
-    fields.group();
+    let fields = Fields::parse(input);
     //println!("{}", &fields.fields);
     fields.price_per_fence()
 }
 
 pub fn part_two(input: &str) -> usize {
-    let mut fields = Fields::parse(input);
-    fields.group();
+    let fields = Fields::parse(input);
     fields.price_per_side()
 }
 
@@ -200,6 +566,7 @@ pub fn part_two(input: &str) -> usize {
 mod tests {
 
     use super::*;
+    use crate::util::rng::Lcg;
 
     #[test]
     fn test_example_big() {
@@ -238,4 +605,108 @@ ABBAAA
 AAAAAA";
         assert_eq!(368, part_two(input));
     }
+
+    #[test]
+    fn test_regions() {
+        let input = "EEEEE
+EXXXX
+EEEEE
+EXXXX
+EEEEE";
+        let regions = Fields::parse(input).regions();
+        assert_eq!(3, regions.len());
+        let e = &regions[0];
+        assert_eq!('E', e.plant);
+        assert_eq!((Position(0, 0), Position(4, 4)), e.bbox);
+        assert_eq!((17, 36, 12), (e.area, e.perimeter, e.sides));
+        assert_eq!(17, e.cells.len());
+        let x = &regions[2];
+        assert_eq!('X', x.plant);
+        assert_eq!((Position(3, 1), Position(3, 4)), x.bbox);
+        assert_eq!((4, 10, 4), (x.area, x.perimeter, x.sides));
+    }
+
+    #[test]
+    fn test_garden_edits() {
+        let input = "RRRRIICCFF
+RRRRIICCCF
+VVRRRCCFFF
+VVRCCCJFFF
+VVVVCJJCFE
+VVIVCCJJEE
+VVIIICJJEE
+MIIIIIJJEE
+MIIISIJEEE
+MMMISSJEEE";
+        let mut garden = Garden::parse(input);
+        assert_eq!(1930, garden.price());
+        // cuts a single plot off the large C region
+        garden.set(Position(3, 4), 'X');
+        assert!(!garden.same_region(Position(3, 3), Position(0, 6)));
+        assert_eq!((1, 4), garden.region(Position(3, 4)));
+        let mut rng = Lcg::new(42);
+        for _ in 0..1000 {
+            let pos = Position(rng.below(10), rng.below(10));
+            let plant = ['R', 'I', 'C', 'F'][rng.below(4)];
+            let expected = Fields::parse(&garden.to_string()).price_per_fence();
+            assert_eq!(expected, garden.price());
+            let price_if = garden.price_if(pos, plant);
+            assert_eq!(expected, garden.price());
+            garden.set(pos, plant);
+            assert_eq!(price_if, garden.price());
+            assert!(garden.stats.len() <= 2 * 100);
+        }
+        assert_eq!(
+            Fields::parse(&garden.to_string()).price_per_fence(),
+            garden.price()
+        );
+    }
+
+    #[test]
+    fn test_region_graph() {
+        let input = "EEEEE
+EXXXX
+EEEEE
+EXXXX
+EEEEE";
+        let graph = Fields::parse(input).region_graph();
+        assert_eq!(Some(&9), graph.fences.get(&(0, 1)));
+        assert_eq!(Some(&9), graph.fences.get(&(0, 2)));
+        // the E region reaches the garden border, so the X regions are not inside it
+        assert!(graph.enclosed.is_empty());
+        let input = "AAAAAA
+AAABBA
+AAABBA
+ABBAAA
+ABBAAA
+AAAAAA";
+        let graph = Fields::parse(input).region_graph();
+        assert_eq!(BTreeMap::from([(1, 0), (2, 0)]), graph.enclosed);
+        assert_eq!(vec![2, 0, 0], graph.holes);
+        // the B regions only touch diagonally
+        assert_eq!(2, graph.fences.len());
+        let input = "OOOOO
+OXXXO
+OXOXO
+OXXXO
+OOOOO";
+        let graph = Fields::parse(input).region_graph();
+        assert_eq!(BTreeMap::from([(1, 0), (2, 1)]), graph.enclosed);
+        assert_eq!(vec![1, 1, 0], graph.holes);
+        assert_eq!(
+            "{\"regions\":[{\"id\":0,\"plant\":\"O\",\"holes\":1,\"enclosed_by\":null},\
+{\"id\":1,\"plant\":\"X\",\"holes\":1,\"enclosed_by\":0},\
+{\"id\":2,\"plant\":\"O\",\"holes\":0,\"enclosed_by\":1}],\
+\"fences\":[{\"regions\":[0,1],\"length\":12},{\"regions\":[1,2],\"length\":4}]}",
+            graph.to_json()
+        );
+        let json = Fields::parse("é\"\\\u{1}").region_graph().to_json();
+        assert!(json.contains("\"plant\":\"é\""));
+        assert!(json.contains("\"plant\":\"\\\"\""));
+        assert!(json.contains("\"plant\":\"\\\\\""));
+        assert!(json.contains("\"plant\":\"\\u0001\""));
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"0\" -- \"1\" [label=\"12\"];"));
+        assert!(dot.contains("\"2\" -- \"1\" [style=\"dashed\"];"));
+    }
 }
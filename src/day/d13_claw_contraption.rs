@@ -31,10 +31,7 @@ impl ClawMachine {
                     return None;
                 }
                 let b = b as usize;
-                let div = match self.x.checked_sub(self.x_b * b) {
-                    Some(div) => div,
-                    None => return None,
-                };
+                let div = self.x.checked_sub(self.x_b * b)?;
                 if div % self.x_a != 0 {
                     return None;
                 }
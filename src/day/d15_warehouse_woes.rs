@@ -42,14 +42,7 @@ impl Map {
         let mut movements = Vec::new();
         for mov in moves.lines() {
             for c in mov.chars() {
-                let dir = match c {
-                    '^' => Direction::Up,
-                    '<' => Direction::Left,
-                    '>' => Direction::Right,
-                    'v' => Direction::Down,
-                    _ => unreachable!(),
-                };
-                movements.push(dir);
+                movements.push(Direction::try_from(c).unwrap());
             }
         }
         Self {
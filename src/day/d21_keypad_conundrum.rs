@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use crate::util::position::{Direction, Position};
+
+const NUMERIC: &str = "789
+456
+123
+ 0A";
+
+const DIRECTIONAL: &str = " ^A
+<v>";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Key {
+    Number(u32),
+    Arrow(Direction),
+    Activate,
+}
+
+impl Key {
+    fn parse(c: char) -> Option<Self> {
+        match c {
+            ' ' => None,
+            'A' => Some(Key::Activate),
+            c => match c.to_digit(10) {
+                Some(number) => Some(Key::Number(number)),
+                None => Some(Key::Arrow(Direction::try_from(c).unwrap())),
+            },
+        }
+    }
+}
+
+struct Keypad {
+    keys: HashMap<Key, Position>,
+    gap: Position,
+}
+
+impl Keypad {
+    fn parse(layout: &str) -> Self {
+        let mut keys = HashMap::new();
+        let mut gap = None;
+        for (idx_row, line) in layout.lines().enumerate() {
+            for (idx_col, c) in line.chars().enumerate() {
+                let pos = Position(idx_row, idx_col);
+                match Key::parse(c) {
+                    Some(key) => {
+                        keys.insert(key, pos);
+                    }
+                    None => gap = Some(pos),
+                }
+            }
+        }
+        Keypad {
+            keys,
+            gap: gap.unwrap(),
+        }
+    }
+
+    /// all shortest key sequences (ending with `Activate`) which move the arm from `from` to `to`
+    /// without pointing at the gap, only straight lines are considered as zig-zag is never cheaper
+    fn paths(&self, from: Key, to: Key) -> Vec<Vec<Key>> {
+        let start = self.keys[&from];
+        let end = self.keys[&to];
+        let vertical = if end.0 > start.0 {
+            vec![Direction::Down; end.0 - start.0]
+        } else {
+            vec![Direction::Up; start.0 - end.0]
+        };
+        let horizontal = if end.1 > start.1 {
+            vec![Direction::Right; end.1 - start.1]
+        } else {
+            vec![Direction::Left; start.1 - end.1]
+        };
+        let mut paths = Vec::new();
+        for moves in [
+            [vertical.as_slice(), horizontal.as_slice()].concat(),
+            [horizontal.as_slice(), vertical.as_slice()].concat(),
+        ] {
+            let mut pos = start;
+            let avoids_gap = moves.iter().all(|&dir| {
+                pos = pos.next(dir).unwrap();
+                pos != self.gap
+            });
+            let mut path: Vec<Key> = moves.into_iter().map(Key::Arrow).collect();
+            path.push(Key::Activate);
+            if avoids_gap && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+}
+
+struct Robots {
+    numeric: Keypad,
+    directional: Keypad,
+    layers: usize,
+    cache: HashMap<(Key, Key, usize), usize>,
+}
+
+impl Robots {
+    fn new(layers: usize) -> Self {
+        Robots {
+            numeric: Keypad::parse(NUMERIC),
+            directional: Keypad::parse(DIRECTIONAL),
+            layers,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// presses the human needs to type `keys` on a directional keypad with `layers` robots in between
+    fn sequence_cost(&mut self, keys: &[Key], layers: usize) -> usize {
+        if layers == 0 {
+            return keys.len();
+        }
+        let mut sum = 0;
+        let mut from = Key::Activate;
+        for &to in keys.iter() {
+            sum += self.move_cost(from, to, layers);
+            from = to;
+        }
+        sum
+    }
+
+    fn move_cost(&mut self, from: Key, to: Key, layers: usize) -> usize {
+        if let Some(&cost) = self.cache.get(&(from, to, layers)) {
+            return cost;
+        }
+        let cost = self
+            .directional
+            .paths(from, to)
+            .iter()
+            .map(|path| self.sequence_cost(path, layers - 1))
+            .min()
+            .unwrap();
+        self.cache.insert((from, to, layers), cost);
+        cost
+    }
+
+    fn presses(&mut self, code: &[Key]) -> usize {
+        let mut sum = 0;
+        let mut from = Key::Activate;
+        for &to in code.iter() {
+            sum += self
+                .numeric
+                .paths(from, to)
+                .iter()
+                .map(|path| self.sequence_cost(path, self.layers))
+                .min()
+                .unwrap();
+            from = to;
+        }
+        sum
+    }
+
+    fn complexity(&mut self, input: &str) -> usize {
+        let mut sum = 0;
+        for line in input.lines() {
+            let code: Vec<Key> = line.chars().map(|c| Key::parse(c).unwrap()).collect();
+            let number: usize = line.trim_end_matches('A').parse().unwrap();
+            sum += number * self.presses(&code);
+        }
+        sum
+    }
+}
+
+pub fn part_one(input: &str) -> usize {
+    let mut robots = Robots::new(2);
+    robots.complexity(input)
+}
+
+pub fn part_two(input: &str) -> usize {
+    let mut robots = Robots::new(25);
+    robots.complexity(input)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let input = "029A
+980A
+179A
+456A
+379A";
+        assert_eq!(126384, part_one(input));
+        assert_eq!(154115708116294, part_two(input));
+    }
+
+    #[test]
+    fn test_presses() {
+        let code: Vec<Key> = "029A".chars().map(|c| Key::parse(c).unwrap()).collect();
+        assert_eq!(12, Robots::new(0).presses(&code));
+        assert_eq!(28, Robots::new(1).presses(&code));
+        assert_eq!(68, Robots::new(2).presses(&code));
+    }
+}
@@ -0,0 +1,107 @@
+use std::iter;
+
+use crate::util::parallel;
+
+const PRUNE: u64 = 16777216 - 1;
+const STEPS: usize = 2000;
+/// price changes are in -9..=9, so a window of four changes has 19^4 encodings
+const WINDOWS: usize = 19 * 19 * 19 * 19;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Secret(u64);
+
+impl Secret {
+    fn parse(input: &str) -> Vec<Self> {
+        input
+            .lines()
+            .map(|line| Secret(line.parse().unwrap()))
+            .collect()
+    }
+
+    fn next(self) -> Self {
+        let mut secret = self.0;
+        secret = (secret ^ (secret << 6)) & PRUNE;
+        secret = (secret ^ (secret >> 5)) & PRUNE;
+        secret = (secret ^ (secret << 11)) & PRUNE;
+        Secret(secret)
+    }
+
+    fn price(self) -> usize {
+        (self.0 % 10) as usize
+    }
+
+    /// the initial secret followed by every evolved one
+    fn evolve(self) -> impl Iterator<Item = Secret> {
+        iter::successors(Some(self), |secret| Some(secret.next()))
+    }
+}
+
+/// bananas per change window, only the first occurrence of a window counts for each buyer
+fn bananas(buyers: &[Secret]) -> Vec<usize> {
+    let mut totals = vec![0; WINDOWS];
+    let mut seen = vec![usize::MAX; WINDOWS];
+    for (buyer, secret) in buyers.iter().enumerate() {
+        let mut window = 0;
+        let mut last = secret.price();
+        for (step, secret) in secret.evolve().skip(1).take(STEPS).enumerate() {
+            let price = secret.price();
+            let change = price + 9 - last;
+            window = (window * 19 + change) % WINDOWS;
+            last = price;
+            if step >= 3 && seen[window] != buyer {
+                seen[window] = buyer;
+                totals[window] += price;
+            }
+        }
+    }
+    totals
+}
+
+pub fn part_one(input: &str) -> u64 {
+    let buyers = Secret::parse(input);
+    parallel::map_chunks(&buyers, |chunk| {
+        chunk
+            .iter()
+            .map(|secret| secret.evolve().nth(STEPS).unwrap().0)
+            .sum::<u64>()
+    })
+    .into_iter()
+    .sum()
+}
+
+pub fn part_two(input: &str) -> usize {
+    let buyers = Secret::parse(input);
+    let mut totals = vec![0; WINDOWS];
+    for chunk_totals in parallel::map_chunks(&buyers, bananas) {
+        for (total, bananas) in totals.iter_mut().zip(chunk_totals) {
+            *total += bananas;
+        }
+    }
+    totals.into_iter().max().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_evolve() {
+        let secrets: Vec<u64> = Secret(123).evolve().skip(1).take(5).map(|s| s.0).collect();
+        assert_eq!(vec![15887950, 16495136, 527345, 704524, 1553684], secrets);
+    }
+
+    #[test]
+    fn test_example() {
+        let input = "1
+10
+100
+2024";
+        assert_eq!(37327623, part_one(input));
+        let input = "1
+2
+3
+2024";
+        assert_eq!(23, part_two(input));
+    }
+}
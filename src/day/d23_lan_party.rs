@@ -0,0 +1,78 @@
+use crate::util::graph::Graph;
+
+fn parse(input: &str) -> Graph<&str> {
+    let mut network = Graph::default();
+    for line in input.lines() {
+        let (a, b) = line.split_once('-').unwrap();
+        network.add_edge(a, b);
+    }
+    network
+}
+
+pub fn part_one(input: &str) -> usize {
+    let network = parse(input);
+    network
+        .triangles()
+        .into_iter()
+        .filter(|triangle| {
+            triangle
+                .iter()
+                .any(|&id| network.node(id).starts_with('t'))
+        })
+        .count()
+}
+
+pub fn part_two(input: &str) -> String {
+    let network = parse(input);
+    let mut party: Vec<&str> = network
+        .max_clique()
+        .into_iter()
+        .map(|id| *network.node(id))
+        .collect();
+    party.sort();
+    party.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let input = "kh-tc
+qp-kh
+de-cg
+ka-co
+yn-aq
+qp-ub
+cg-tb
+vc-aq
+tb-ka
+wh-tc
+yn-cg
+kh-ub
+ta-co
+de-co
+tc-td
+tb-wq
+wh-td
+ta-ka
+td-qp
+aq-cg
+wq-ub
+ub-vc
+de-ta
+wq-aq
+wq-vc
+wh-yn
+ka-de
+kh-ta
+co-tc
+wh-qp
+tb-vc
+td-yn";
+        assert_eq!(7, part_one(input));
+        assert_eq!("co,de,ka,ta", part_two(input));
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::util::dot::Dot;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    And,
+    Or,
+    Xor,
+}
+
+impl Op {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Op::And => a & b,
+            Op::Or => a | b,
+            Op::Xor => a ^ b,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gate<'a> {
+    pub a: &'a str,
+    pub b: &'a str,
+    pub op: Op,
+    pub out: &'a str,
+}
+
+impl Gate<'_> {
+    fn has_input(&self, wire: &str) -> bool {
+        self.a == wire || self.b == wire
+    }
+
+    fn has_xy_input(&self) -> bool {
+        is_xy(self.a) && is_xy(self.b)
+    }
+}
+
+fn is_xy(wire: &str) -> bool {
+    wire.starts_with('x') || wire.starts_with('y')
+}
+
+pub struct Circuit<'a> {
+    inputs: HashMap<&'a str, bool>,
+    gates: Vec<Gate<'a>>,
+    outputs: HashMap<&'a str, usize>,
+}
+
+impl<'a> Circuit<'a> {
+    pub fn parse(input: &'a str) -> Self {
+        let (in_wires, in_gates) = input.split_once("\n\n").unwrap();
+        let mut inputs = HashMap::new();
+        for line in in_wires.lines() {
+            let (wire, value) = line.split_once(": ").unwrap();
+            inputs.insert(wire, value == "1");
+        }
+        let mut gates = Vec::new();
+        let mut outputs = HashMap::new();
+        for line in in_gates.lines() {
+            let (expr, out) = line.split_once(" -> ").unwrap();
+            let mut iter = expr.split_whitespace();
+            let a = iter.next().unwrap();
+            let op = match iter.next().unwrap() {
+                "AND" => Op::And,
+                "OR" => Op::Or,
+                "XOR" => Op::Xor,
+                _ => unreachable!(),
+            };
+            let b = iter.next().unwrap();
+            outputs.insert(out, gates.len());
+            gates.push(Gate { a, b, op, out });
+        }
+        Circuit {
+            inputs,
+            gates,
+            outputs,
+        }
+    }
+
+    fn value(&self, wire: &'a str, values: &mut HashMap<&'a str, bool>) -> bool {
+        if let Some(&value) = values.get(wire) {
+            return value;
+        }
+        let gate = &self.gates[self.outputs[wire]];
+        let a = self.value(gate.a, values);
+        let b = self.value(gate.b, values);
+        let value = gate.op.apply(a, b);
+        values.insert(wire, value);
+        value
+    }
+
+    fn z_wires(&self) -> Vec<&'a str> {
+        let mut z_wires: Vec<&str> = self
+            .outputs
+            .keys()
+            .copied()
+            .filter(|wire| wire.starts_with('z'))
+            .collect();
+        z_wires.sort();
+        z_wires
+    }
+
+    pub fn simulate(&self) -> usize {
+        let mut values = self.inputs.clone();
+        let mut number = 0;
+        for wire in self.z_wires().into_iter().rev() {
+            number = number << 1 | self.value(wire, &mut values) as usize;
+        }
+        number
+    }
+
+    /// value of a wire with the given outputs swapped, `None` if the swaps close a loop
+    ///
+    /// `values` starts out with the inputs, wires being evaluated are kept as `None`
+    fn swapped_value(
+        &self,
+        wire: &'a str,
+        swaps: &HashMap<&'a str, &'a str>,
+        values: &mut HashMap<&'a str, Option<bool>>,
+    ) -> Option<bool> {
+        if let Some(&value) = values.get(wire) {
+            return value;
+        }
+        values.insert(wire, None);
+        let driver = swaps.get(wire).copied().unwrap_or(wire);
+        let gate = &self.gates[self.outputs[driver]];
+        let a = self.swapped_value(gate.a, swaps, values)?;
+        let b = self.swapped_value(gate.b, swaps, values)?;
+        let value = gate.op.apply(a, b);
+        values.insert(wire, Some(value));
+        Some(value)
+    }
+
+    /// whether the circuit adds `x` and `y` with the given outputs swapped
+    fn adds(&self, x: u64, y: u64, swaps: &HashMap<&'a str, &'a str>) -> bool {
+        let mut values: HashMap<&str, Option<bool>> = self
+            .inputs
+            .keys()
+            .map(|&wire| {
+                let number = if wire.starts_with('x') { x } else { y };
+                let bit: u32 = wire[1..].parse().unwrap();
+                (wire, Some(number >> bit & 1 == 1))
+            })
+            .collect();
+        let sum = x as u128 + y as u128;
+        self.z_wires().into_iter().enumerate().all(|(bit, wire)| {
+            self.swapped_value(wire, swaps, &mut values) == Some(sum >> bit & 1 == 1)
+        })
+    }
+
+    /// whether the circuit adds correctly with the given outputs swapped, checked on additions
+    /// which set, carry into and carry out of every bit
+    fn is_adder(&self, swaps: &HashMap<&'a str, &'a str>) -> bool {
+        let bits = self
+            .inputs
+            .keys()
+            .filter(|wire| wire.starts_with('x'))
+            .count() as u32;
+        let ones = u64::MAX.checked_shr(64 - bits).unwrap_or(0);
+        let mut additions = vec![
+            (0, 0),
+            (ones, 1),
+            (ones, ones),
+            (ones & 0x5555_5555_5555_5555, ones & !0x5555_5555_5555_5555),
+        ];
+        for bit in 0..bits {
+            additions.extend([(1 << bit, 0), (0, 1 << bit), (1 << bit, 1 << bit)]);
+        }
+        additions.into_iter().all(|(x, y)| self.adds(x, y, swaps))
+    }
+
+    /// pairs of swapped outputs which turn the circuit into an adder, `None` if the suspicious
+    /// wires can not be paired up like that
+    pub fn swapped_pairs(&self) -> Option<Vec<(&'a str, &'a str)>> {
+        let suspicious: Vec<&str> = self.suspicious_wires().into_iter().collect();
+        let mut pairs = Vec::new();
+        self.pair_up(&suspicious, &mut pairs).then_some(pairs)
+    }
+
+    fn pair_up(&self, wires: &[&'a str], pairs: &mut Vec<(&'a str, &'a str)>) -> bool {
+        let Some((&first, rest)) = wires.split_first() else {
+            let swaps = pairs.iter().flat_map(|&(a, b)| [(a, b), (b, a)]).collect();
+            return self.is_adder(&swaps);
+        };
+        for idx in 0..rest.len() {
+            let mut others = rest.to_vec();
+            let second = others.remove(idx);
+            pairs.push((first, second));
+            if self.pair_up(&others, pairs) {
+                return true;
+            }
+            pairs.pop();
+        }
+        false
+    }
+
+    fn feeds(&self, wire: &str, op: Op) -> bool {
+        self.gates
+            .iter()
+            .any(|gate| gate.op == op && gate.has_input(wire))
+    }
+
+    /// outputs which break the ripple-carry adder structure
+    ///
+    /// bit i: `x XOR y -> s`, `x AND y -> a`, `s XOR carry -> z`, `s AND carry -> t`,
+    /// `a OR t -> carry`, bit 0 is a half adder and the last carry is the highest z
+    ///
+    /// each gate is only judged by the rules for its own type, so two outputs in the same role
+    /// swapped between bits, like `z03` and `z07`, still look right and are not reported
+    pub fn suspicious_wires(&self) -> BTreeSet<&'a str> {
+        let last_z = *self.z_wires().last().unwrap();
+        let mut suspicious = BTreeSet::new();
+        for gate in self.gates.iter() {
+            let is_first_bit = gate.has_input("x00");
+            let drives_sum = gate.out.starts_with('z') && gate.out != last_z;
+            let is_wrong = match gate.op {
+                _ if drives_sum => gate.op != Op::Xor,
+                Op::Xor if gate.has_xy_input() => is_first_bit || !self.feeds(gate.out, Op::Xor),
+                Op::Xor => true,
+                Op::And => !is_first_bit && !self.feeds(gate.out, Op::Or),
+                // a carry feeds the next bit's sum and carry, except for the last one
+                Op::Or => {
+                    gate.out != last_z
+                        && !(self.feeds(gate.out, Op::Xor) && self.feeds(gate.out, Op::And))
+                }
+            };
+            if is_wrong {
+                suspicious.insert(gate.out);
+            }
+        }
+        suspicious
+    }
+
+    /// gate network as DOT graph, suspicious wires are highlighted
+    pub fn to_dot(&self) -> String {
+        let suspicious = self.suspicious_wires();
+        let mut dot = Dot::digraph();
+        for (idx, gate) in self.gates.iter().enumerate() {
+            let id = format!("gate{}", idx);
+            let label = format!("{:?}", gate.op).to_uppercase();
+            dot.node(&id, &[("label", &label), ("shape", "box")]);
+            dot.edge(gate.a, &id, &[]);
+            dot.edge(gate.b, &id, &[]);
+            dot.edge(&id, gate.out, &[]);
+            if suspicious.contains(gate.out) {
+                dot.node(gate.out, &[("color", "red")]);
+            }
+        }
+        dot.to_string()
+    }
+}
+
+pub fn part_one(input: &str) -> usize {
+    let circuit = Circuit::parse(input);
+    circuit.simulate()
+}
+
+/// `None` if the suspicious wires do not pair up into four swaps which repair the adder
+pub fn part_two(input: &str) -> Option<String> {
+    let circuit = Circuit::parse(input);
+    let pairs = circuit.swapped_pairs()?;
+    if pairs.len() != 4 {
+        return None;
+    }
+    let swapped: BTreeSet<&str> = pairs.into_iter().flat_map(|(a, b)| [a, b]).collect();
+    Some(swapped.into_iter().collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let input = "x00: 1
+x01: 1
+x02: 1
+y00: 0
+y01: 1
+y02: 0
+
+x00 AND y00 -> z00
+x01 XOR y01 -> z01
+x02 OR y02 -> z02";
+        assert_eq!(4, part_one(input));
+    }
+
+    #[test]
+    fn test_example_big() {
+        let input = "x00: 1
+x01: 0
+x02: 1
+x03: 1
+x04: 0
+y00: 1
+y01: 1
+y02: 1
+y03: 1
+y04: 1
+
+ntg XOR fgs -> mjb
+y02 OR x01 -> tnw
+kwq OR kpj -> z05
+x00 OR x03 -> fst
+tgd XOR rvg -> z01
+vdt OR tnw -> bfw
+bfw AND frj -> z10
+ffh OR nrd -> bqk
+y00 AND y03 -> djm
+y03 OR y00 -> psh
+bqk OR frj -> z08
+tnw OR fst -> frj
+gnj AND tgd -> z11
+bfw XOR mjb -> z00
+x03 OR x00 -> vdt
+gnj AND wpb -> z02
+x04 AND y00 -> kjc
+djm OR pbm -> qhw
+nrd AND vdt -> hwm
+kjc AND fst -> rvg
+y04 OR y02 -> fgs
+y01 AND x02 -> pbm
+ntg OR kjc -> kwq
+psh XOR fgs -> tgd
+qhw XOR tgd -> z09
+pbm OR djm -> kpj
+x03 XOR y03 -> ffh
+x00 XOR y04 -> ntg
+bfw OR bqk -> z06
+nrd XOR fgs -> wpb
+frj XOR qhw -> z04
+bqk OR frj -> z07
+y03 OR x01 -> nrd
+hwm AND bqk -> z03
+tgd XOR rvg -> z12
+tnw OR pbm -> gnj";
+        assert_eq!(2024, part_one(input));
+    }
+
+    fn adder(bits: usize, x: usize, y: usize, swaps: &[(&str, &str)]) -> String {
+        let mut lines = Vec::new();
+        for i in 0..bits {
+            lines.push(format!("x{:02}: {}", i, x >> i & 1));
+        }
+        for i in 0..bits {
+            lines.push(format!("y{:02}: {}", i, y >> i & 1));
+        }
+        lines.push(String::new());
+        let mut gates = vec![
+            ("x00 XOR y00".to_string(), "z00".to_string()),
+            ("x00 AND y00".to_string(), "c00".to_string()),
+        ];
+        for i in 1..bits {
+            let carry_in = format!("c{:02}", i - 1);
+            let carry_out = if i == bits - 1 {
+                format!("z{:02}", bits)
+            } else {
+                format!("c{:02}", i)
+            };
+            gates.push((format!("x{i:02} XOR y{i:02}"), format!("s{i:02}")));
+            gates.push((format!("x{i:02} AND y{i:02}"), format!("a{i:02}")));
+            gates.push((format!("s{i:02} XOR {carry_in}"), format!("z{i:02}")));
+            gates.push((format!("s{i:02} AND {carry_in}"), format!("t{i:02}")));
+            gates.push((format!("a{i:02} OR t{i:02}"), carry_out));
+        }
+        for (expr, mut out) in gates {
+            for &(a, b) in swaps {
+                if out == a {
+                    out = b.to_string();
+                } else if out == b {
+                    out = a.to_string();
+                }
+            }
+            lines.push(format!("{} -> {}", expr, out));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_adder() {
+        let input = adder(8, 173, 99, &[]);
+        assert_eq!(173 + 99, part_one(&input));
+        assert!(Circuit::parse(&input).suspicious_wires().is_empty());
+    }
+
+    #[test]
+    fn test_swapped_adder() {
+        let swaps = [
+            ("z02", "c02"),
+            ("s04", "a04"),
+            ("z05", "t05"),
+            ("z06", "a07"),
+        ];
+        let input = adder(8, 173, 99, &swaps);
+        assert_eq!(
+            Some("a04,a07,c02,s04,t05,z02,z05,z06".to_string()),
+            part_two(&input)
+        );
+        let dot = Circuit::parse(&input).to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("\"z05\" [color=\"red\"];"));
+        // a carry swapped with the AND of the next input bits
+        let swaps = [
+            ("c02", "a03"),
+            ("s04", "a04"),
+            ("z05", "t05"),
+            ("z06", "a07"),
+        ];
+        let input = adder(8, 173, 99, &swaps);
+        assert_eq!(
+            Some("a03,a04,a07,c02,s04,t05,z05,z06".to_string()),
+            part_two(&input)
+        );
+        // two sums swapped between bits break no rule of their own gate type
+        let swaps = [
+            ("z03", "z06"),
+            ("s04", "a04"),
+            ("z05", "t05"),
+            ("c02", "a03"),
+        ];
+        let input = adder(8, 173, 99, &swaps);
+        let circuit = Circuit::parse(&input);
+        assert!(!circuit.suspicious_wires().contains("z03"));
+        assert_ne!(173 + 99, circuit.simulate());
+        assert_eq!(None, part_two(&input));
+    }
+}
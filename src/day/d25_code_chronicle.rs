@@ -0,0 +1,123 @@
+const COLUMNS: usize = 5;
+/// rows between the solid top and bottom row of a schematic
+const SPACE: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Lock,
+    Key,
+}
+
+struct Schematic {
+    kind: Kind,
+    heights: [usize; COLUMNS],
+}
+
+impl Schematic {
+    fn parse(input: &str) -> Vec<Self> {
+        let mut schematics = Vec::new();
+        for block in input.split("\n\n") {
+            let rows: Vec<&str> = block.lines().collect();
+            let kind = if rows[0].starts_with('#') {
+                Kind::Lock
+            } else {
+                Kind::Key
+            };
+            let mut heights = [0; COLUMNS];
+            for row in rows[1..=SPACE].iter() {
+                for (height, c) in heights.iter_mut().zip(row.chars()) {
+                    if c == '#' {
+                        *height += 1;
+                    }
+                }
+            }
+            schematics.push(Schematic { kind, heights });
+        }
+        schematics
+    }
+
+    /// pins as bits, a lock fills its column from the top and a key from the bottom,
+    /// so a pair fits if their masks don't overlap
+    fn mask(&self) -> u32 {
+        let mut mask = 0;
+        for (col, &height) in self.heights.iter().enumerate() {
+            let mut column = (1 << height) - 1;
+            if self.kind == Kind::Key {
+                column <<= SPACE - height;
+            }
+            mask |= column << (col * SPACE);
+        }
+        mask
+    }
+}
+
+pub fn part_one(input: &str) -> usize {
+    let schematics = Schematic::parse(input);
+    let masks = |kind| -> Vec<u32> {
+        schematics
+            .iter()
+            .filter(|schematic| schematic.kind == kind)
+            .map(|schematic| schematic.mask())
+            .collect()
+    };
+    let locks = masks(Kind::Lock);
+    let keys = masks(Kind::Key);
+    let mut fitting = 0;
+    for lock in locks.iter() {
+        fitting += keys.iter().filter(|&key| lock & key == 0).count();
+    }
+    fitting
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let input = "#####
+.####
+.####
+.####
+.#.#.
+.#...
+.....
+
+#####
+##.##
+.#.##
+...##
+...#.
+...#.
+.....
+
+.....
+#....
+#....
+#...#
+#.#.#
+#.###
+#####
+
+.....
+.....
+#.#..
+###..
+###.#
+###.#
+#####
+
+.....
+.....
+.....
+#....
+#.#..
+#.#.#
+#####";
+        let schematics = Schematic::parse(input);
+        assert_eq!([0, 5, 3, 4, 3], schematics[0].heights);
+        assert_eq!([5, 0, 2, 1, 3], schematics[2].heights);
+        assert_eq!(3, part_one(input));
+    }
+}
@@ -1,4 +1,9 @@
-struct Report(Vec<isize>);
+use std::ops::RangeInclusive;
+
+/// allowed difference between two adjacent levels
+const STEPS: RangeInclusive<isize> = 1..=3;
+
+pub struct Report(pub Vec<isize>);
 
 struct Reports(Vec<Report>);
 
@@ -28,41 +33,69 @@ impl Reports {
 }
 
 impl Report {
-    fn is_safe(&self) -> bool {
-        let mut levels = self.0.iter();
-        let mut last = levels.next().cloned().unwrap();
-        let mut incr_state = None;
-        for &level in levels {
-            let diff = level - last;
-            if diff.abs() > 3 || diff == 0 {
-                return false;
-            }
-            match (incr_state, diff.is_positive()) {
-                (Some(prev_incr), incr) if prev_incr == incr => {}
-                (None, is_incr) => {
-                    incr_state = Some(is_incr);
+    /// fewest levels to remove so the remaining ones change monotonically by a step within
+    /// `steps`, `None` if that takes more than `tolerance` removals
+    ///
+    /// a kept level can only follow one of the `tolerance + 1` levels before it, so the search
+    /// is linear in the number of levels for a fixed tolerance
+    pub fn removals(&self, steps: &RangeInclusive<isize>, tolerance: usize) -> Option<Vec<usize>> {
+        let levels = &self.0;
+        let len = levels.len();
+        if len == 0 {
+            return Some(Vec::new());
+        }
+        let mut best: Option<Vec<usize>> = None;
+        for sign in [1, -1] {
+            // fewest removals up to a level which is kept, and the kept level before it
+            let mut removed = vec![usize::MAX; len];
+            let mut previous = vec![None; len];
+            for idx in 0..len {
+                if idx <= tolerance {
+                    removed[idx] = idx;
                 }
-                _ => {
-                    return false;
+                for prev in idx.saturating_sub(tolerance + 1)..idx {
+                    if removed[prev] == usize::MAX
+                        || !steps.contains(&(sign * (levels[idx] - levels[prev])))
+                    {
+                        continue;
+                    }
+                    let cost = removed[prev] + idx - prev - 1;
+                    if cost < removed[idx] {
+                        removed[idx] = cost;
+                        previous[idx] = Some(prev);
+                    }
                 }
             }
-            last = level;
+            // everything after the last kept level is removed as well
+            let Some(last) = (len.saturating_sub(tolerance + 1)..len)
+                .filter(|&idx| removed[idx] != usize::MAX)
+                .min_by_key(|&idx| removed[idx] + len - 1 - idx)
+            else {
+                continue;
+            };
+            if removed[last] + len - 1 - last > tolerance {
+                continue;
+            }
+            let mut kept = vec![false; len];
+            let mut idx = Some(last);
+            while let Some(keep) = idx {
+                kept[keep] = true;
+                idx = previous[keep];
+            }
+            let removals: Vec<usize> = (0..len).filter(|&idx| !kept[idx]).collect();
+            if best.as_ref().is_none_or(|best| removals.len() < best.len()) {
+                best = Some(removals);
+            }
         }
-        true
+        best
+    }
+
+    fn is_safe(&self) -> bool {
+        self.removals(&STEPS, 0).is_some()
     }
 
     fn is_safe_tolerate(&self) -> bool {
-        if self.is_safe() {
-            return true;
-        }
-        for (i, _v) in self.0.iter().enumerate() {
-            let mut vec = self.0.clone();
-            vec.remove(i);
-            if Self(vec).is_safe() {
-                return true;
-            }
-        }
-        false
+        self.removals(&STEPS, 1).is_some()
     }
 }
 
@@ -79,6 +112,7 @@ pub fn part_two(input: &str) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::rng::Lcg;
 
     #[test]
     fn test_safe() {
@@ -91,4 +125,45 @@ mod tests {
         assert_eq!(part_one(input), 2, "2.1 failed");
         assert_eq!(part_two(input), 4, "2.2 failed");
     }
+
+    #[test]
+    fn test_removals() {
+        let report = |levels: &str| Reports::parse(levels).0.pop().unwrap();
+        assert_eq!(Some(vec![]), report("7 6 4 2 1").removals(&STEPS, 0));
+        assert_eq!(None, report("1 2 7 8 9").removals(&STEPS, 1));
+        assert_eq!(Some(vec![2]), report("1 3 2 4 5").removals(&STEPS, 1));
+        assert_eq!(Some(vec![1, 3]), report("1 9 2 9 3").removals(&STEPS, 2));
+        assert_eq!(None, report("1 9 2 9 3").removals(&STEPS, 1));
+        assert_eq!(Some(vec![2]), report("2 4 5 6 8").removals(&(2..=2), 1));
+        // compare the fewest removals with trying every subset
+        let mut rng = Lcg::new(7);
+        for _ in 0..300 {
+            let levels: Vec<isize> = (0..7).map(|_| rng.below(9) as isize).collect();
+            let fewest = (0_u32..1 << levels.len())
+                .filter(|mask| {
+                    let kept: Vec<isize> = levels
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| mask & (1 << idx) == 0)
+                        .map(|(_, &level)| level)
+                        .collect();
+                    // checked pair by pair rather than through `removals`
+                    let diffs: Vec<isize> = kept.windows(2).map(|w| w[1] - w[0]).collect();
+                    diffs.iter().all(|diff| (1..=3).contains(diff))
+                        || diffs.iter().all(|diff| (-3..=-1).contains(diff))
+                })
+                .map(|mask| mask.count_ones() as usize)
+                .min()
+                .unwrap();
+            let len = levels.len();
+            let report = Report(levels);
+            for tolerance in 0..len {
+                let removals = report.removals(&STEPS, tolerance);
+                assert_eq!(fewest <= tolerance, removals.is_some());
+                if let Some(removals) = removals {
+                    assert_eq!(fewest, removals.len());
+                }
+            }
+        }
+    }
 }
@@ -1,94 +1,472 @@
-use Instruction::*;
-enum Instruction {
-    Do,
-    Dont,
-    Mul(isize),
-}
-
-impl Instruction {
-    fn parse(input: &str) -> Vec<Self> {
-        let mut instrutions = Vec::new();
-        for (pos, _do) in input.match_indices("do()") {
-            instrutions.push((pos, Do));
-        }
-        for (pos, _dont) in input.match_indices("don't()") {
-            instrutions.push((pos, Dont));
+use std::{
+    fmt::{Display, Write},
+    ops::Range,
+};
+
+/// an instruction kind the lexer recognises as `name(operand,...)`
+pub struct Definition {
+    pub name: &'static str,
+    pub arity: usize,
+    /// digits allowed per operand
+    pub max_digits: usize,
+    /// `None` if the accumulator would overflow, the machine is left unchanged then
+    pub effect: fn(&mut Machine, &[isize]) -> Option<()>,
+}
+
+pub const MUL: Definition = Definition {
+    name: "mul",
+    arity: 2,
+    max_digits: 3,
+    effect: Machine::mul,
+};
+
+pub const ADD: Definition = Definition {
+    name: "add",
+    arity: 2,
+    max_digits: 3,
+    effect: Machine::add,
+};
+
+pub const DO: Definition = Definition {
+    name: "do",
+    arity: 0,
+    max_digits: 0,
+    effect: Machine::enable,
+};
+
+pub const DONT: Definition = Definition {
+    name: "don't",
+    arity: 0,
+    max_digits: 0,
+    effect: Machine::disable,
+};
+
+/// `do()` only closes the innermost `don't()`
+pub const NESTED_DO: Definition = Definition {
+    effect: Machine::close_disabled,
+    ..DO
+};
+
+pub const NESTED_DONT: Definition = Definition {
+    effect: Machine::open_disabled,
+    ..DONT
+};
+
+#[derive(Default, Debug)]
+pub struct Machine {
+    pub accumulator: isize,
+    /// number of open `don't()` scopes
+    pub disabled: usize,
+}
+
+impl Machine {
+    pub fn is_enabled(&self) -> bool {
+        self.disabled == 0
+    }
+
+    pub fn mul(&mut self, operands: &[isize]) -> Option<()> {
+        if self.is_enabled() {
+            let product = operands
+                .iter()
+                .try_fold(1_isize, |product, &operand| product.checked_mul(operand))?;
+            self.accumulator = self.accumulator.checked_add(product)?;
         }
-        for (pos, _mul) in input.match_indices("mul(") {
-            let ltrim = &input[pos + 4..];
-            let trimmed = match ltrim.find(")") {
-                None => continue,
-                Some(i) => ltrim.get(..i).unwrap(),
-            };
+        Some(())
+    }
 
-            if let Some(_hit) = trimmed.find(|c: char| !(c.is_numeric() || c == ',')) {
-                //invalid character
-                continue;
-            }
+    pub fn add(&mut self, operands: &[isize]) -> Option<()> {
+        if self.is_enabled() {
+            let sum = operands
+                .iter()
+                .try_fold(0_isize, |sum, &operand| sum.checked_add(operand))?;
+            self.accumulator = self.accumulator.checked_add(sum)?;
+        }
+        Some(())
+    }
 
-            if 1 != trimmed.chars().filter(|&c| c == ',').count() {
-                continue;
-            }
+    pub fn enable(&mut self, _operands: &[isize]) -> Option<()> {
+        self.disabled = 0;
+        Some(())
+    }
 
-            let (lhs, rhs) = trimmed.split_once(',').unwrap();
+    pub fn disable(&mut self, _operands: &[isize]) -> Option<()> {
+        self.disabled = 1;
+        Some(())
+    }
 
-            if !lhs.is_valid() || !rhs.is_valid() {
-                continue;
-            }
+    pub fn open_disabled(&mut self, _operands: &[isize]) -> Option<()> {
+        self.disabled += 1;
+        Some(())
+    }
 
-            let lhs: isize = lhs.parse().unwrap();
-            let rhs: isize = rhs.parse().unwrap();
+    pub fn close_disabled(&mut self, _operands: &[isize]) -> Option<()> {
+        self.disabled = self.disabled.saturating_sub(1);
+        Some(())
+    }
 
-            instrutions.push((pos, Mul(lhs * rhs)));
+    pub fn run(definitions: &[Definition], input: &str) -> Result<Self, Overflow> {
+        let mut machine = Machine::default();
+        for token in Lexer::new(input, definitions) {
+            let instruction = token.instruction;
+            (instruction.definition.effect)(&mut machine, &instruction.operands).ok_or(
+                Overflow {
+                    pos: token.span.start,
+                },
+            )?;
         }
+        Ok(machine)
+    }
+}
 
-        instrutions.sort_by_key(|(pos, _)| *pos);
-        instrutions.into_iter().map(|(_, instr)| instr).collect()
+/// the accumulator no longer fits into `isize` after the instruction at this byte
+#[derive(Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub pos: usize,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "accumulator overflows at byte {}", self.pos)
     }
 }
 
-pub fn part_one(input: &str) -> isize {
-    let instuctions = Instruction::parse(input);
-    let mut sum = 0;
-    for instruction in instuctions.into_iter() {
-        if let Mul(mul) = instruction {
-            sum += mul;
+pub struct Instruction<'a> {
+    pub definition: &'a Definition,
+    pub operands: Vec<isize>,
+}
+
+pub struct Token<'a> {
+    pub instruction: Instruction<'a>,
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reason {
+    Unexpected(char),
+    TooManyDigits,
+    /// an operand or the accumulator no longer fits into `isize`
+    Overflow,
+    EmptyOperand,
+    MissingComma,
+    TooManyOperands,
+    Unterminated,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Unexpected(c) => write!(f, "unexpected {:?}", c),
+            Reason::TooManyDigits => f.write_str("too many digits"),
+            Reason::Overflow => f.write_str("overflow"),
+            Reason::EmptyOperand => f.write_str("empty operand"),
+            Reason::MissingComma => f.write_str("missing comma"),
+            Reason::TooManyOperands => f.write_str("too many operands"),
+            Reason::Unterminated => f.write_str("missing ')'"),
         }
     }
-    sum
 }
 
-pub fn part_two(input: &str) -> isize {
-    let instuctions = Instruction::parse(input);
-    let mut sum = 0;
-    let mut mul_enabled = true;
-    for instruction in instuctions.into_iter() {
-        match (instruction, mul_enabled) {
-            (Do, _) => {
-                mul_enabled = true;
+/// a candidate which matched `name(` but broke before its closing parenthesis,
+/// the span ends before the offending byte
+pub struct Rejection<'a> {
+    pub definition: &'a Definition,
+    pub span: Range<usize>,
+    pub reason: Reason,
+}
+
+pub enum Lexeme<'a> {
+    Accepted(Token<'a>),
+    Rejected(Rejection<'a>),
+}
+
+enum State {
+    Start,
+    Name,
+    Operands {
+        definition: usize,
+        operands: Vec<isize>,
+        value: isize,
+        digits: usize,
+    },
+}
+
+enum Step<'a> {
+    Pending,
+    Emit(Token<'a>),
+    /// the bytes so far are not the start of any instruction
+    Restart,
+    Reject(Reason),
+}
+
+/// single pass over the bytes, a rejected candidate restarts at the byte which broke it and
+/// a broken name one byte after its start, as another name might begin inside it
+pub struct Lexer<'a> {
+    input: &'a str,
+    definitions: &'a [Definition],
+    /// definition names including the opening parenthesis
+    keywords: Vec<String>,
+    pos: usize,
+    start: usize,
+    state: State,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str, definitions: &'a [Definition]) -> Self {
+        Lexer {
+            input,
+            definitions,
+            keywords: definitions
+                .iter()
+                .map(|definition| format!("{}(", definition.name))
+                .collect(),
+            pos: 0,
+            start: 0,
+            state: State::Start,
+        }
+    }
+
+    fn step(&mut self, byte: u8) -> Step<'a> {
+        match &mut self.state {
+            State::Start => {
+                if self.keywords.iter().any(|k| k.as_bytes()[0] == byte) {
+                    self.start = self.pos;
+                    self.state = State::Name;
+                }
+                Step::Pending
+            }
+            State::Name => {
+                let name = &self.input.as_bytes()[self.start..=self.pos];
+                if let Some(definition) = self.keywords.iter().position(|k| k.as_bytes() == name) {
+                    self.state = State::Operands {
+                        definition,
+                        operands: Vec::new(),
+                        value: 0,
+                        digits: 0,
+                    };
+                    Step::Pending
+                } else if self.keywords.iter().any(|k| k.as_bytes().starts_with(name)) {
+                    Step::Pending
+                } else {
+                    Step::Restart
+                }
             }
-            (Dont, _) => {
-                mul_enabled = false;
+            State::Operands {
+                definition,
+                operands,
+                value,
+                digits,
+            } => {
+                let Definition {
+                    arity, max_digits, ..
+                } = self.definitions[*definition];
+                match byte {
+                    b'0'..=b'9' if operands.len() == arity => Step::Reject(Reason::TooManyOperands),
+                    b'0'..=b'9' if *digits == max_digits => Step::Reject(Reason::TooManyDigits),
+                    b'0'..=b'9' => {
+                        let digit = (byte - b'0') as isize;
+                        match value.checked_mul(10).and_then(|v| v.checked_add(digit)) {
+                            Some(next) => {
+                                *value = next;
+                                *digits += 1;
+                                Step::Pending
+                            }
+                            None => Step::Reject(Reason::Overflow),
+                        }
+                    }
+                    b',' | b')' if *digits == 0 && arity > 0 => Step::Reject(Reason::EmptyOperand),
+                    b',' if operands.len() + 1 >= arity => Step::Reject(Reason::TooManyOperands),
+                    b',' => {
+                        operands.push(*value);
+                        *value = 0;
+                        *digits = 0;
+                        Step::Pending
+                    }
+                    b')' if arity == 0 => self.emit(),
+                    b')' if operands.len() + 1 < arity => Step::Reject(Reason::MissingComma),
+                    b')' => {
+                        operands.push(*value);
+                        self.emit()
+                    }
+                    _ => {
+                        let c = self.input[self.pos..].chars().next().unwrap();
+                        Step::Reject(Reason::Unexpected(c))
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self) -> Step<'a> {
+        let State::Operands {
+            definition,
+            operands,
+            ..
+        } = std::mem::replace(&mut self.state, State::Start)
+        else {
+            unreachable!()
+        };
+        Step::Emit(Token {
+            instruction: Instruction {
+                definition: &self.definitions[definition],
+                operands,
+            },
+            span: self.start..self.pos + 1,
+        })
+    }
+
+    fn reject(&mut self, reason: Reason) -> Rejection<'a> {
+        let State::Operands { definition, .. } = std::mem::replace(&mut self.state, State::Start)
+        else {
+            unreachable!()
+        };
+        Rejection {
+            definition: &self.definitions[definition],
+            span: self.start..self.pos,
+            reason,
+        }
+    }
+
+    fn next_lexeme(&mut self) -> Option<Lexeme<'a>> {
+        while let Some(&byte) = self.input.as_bytes().get(self.pos) {
+            match self.step(byte) {
+                Step::Pending => self.pos += 1,
+                Step::Emit(token) => {
+                    self.pos += 1;
+                    return Some(Lexeme::Accepted(token));
+                }
+                Step::Restart => {
+                    self.pos = self.start + 1;
+                    self.state = State::Start;
+                }
+                Step::Reject(reason) => return Some(Lexeme::Rejected(self.reject(reason))),
             }
-            (Mul(mul), true) => {
-                sum += mul;
+        }
+        if let State::Operands { .. } = self.state {
+            return Some(Lexeme::Rejected(self.reject(Reason::Unterminated)));
+        }
+        None
+    }
+
+    /// accepted tokens and rejected candidates in input order
+    pub fn lexemes(mut self) -> impl Iterator<Item = Lexeme<'a>> {
+        std::iter::from_fn(move || self.next_lexeme())
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(lexeme) = self.next_lexeme() {
+            if let Lexeme::Accepted(token) = lexeme {
+                return Some(token);
             }
-            _ => {}
         }
+        None
     }
-    sum
 }
 
-trait ValidNumber {
-    fn is_valid(&self) -> bool;
+pub struct Annotation<'a> {
+    pub name: &'a str,
+    pub span: Range<usize>,
+    pub verdict: Result<(), Reason>,
+    /// machine state when the candidate was reached
+    pub enabled: bool,
 }
 
-impl ValidNumber for str {
-    fn is_valid(&self) -> bool {
-        !self.is_empty() && self.len() <= 3
+/// every candidate in the memory with the reason it was accepted or rejected
+pub struct Report<'a> {
+    input: &'a str,
+    pub annotations: Vec<Annotation<'a>>,
+}
+
+impl<'a> Report<'a> {
+    pub fn annotate(definitions: &'a [Definition], input: &'a str) -> Self {
+        let mut machine = Machine::default();
+        let mut annotations = Vec::new();
+        for lexeme in Lexer::new(input, definitions).lexemes() {
+            let enabled = machine.is_enabled();
+            let annotation = match lexeme {
+                Lexeme::Accepted(token) => {
+                    let instruction = token.instruction;
+                    let verdict =
+                        (instruction.definition.effect)(&mut machine, &instruction.operands)
+                            .ok_or(Reason::Overflow);
+                    Annotation {
+                        name: instruction.definition.name,
+                        span: token.span,
+                        verdict,
+                        enabled,
+                    }
+                }
+                Lexeme::Rejected(rejection) => Annotation {
+                    name: rejection.definition.name,
+                    span: rejection.span,
+                    verdict: Err(rejection.reason),
+                    enabled,
+                },
+            };
+            annotations.push(annotation);
+        }
+        Report { input, annotations }
+    }
+
+    /// the input with accepted instructions green (yellow if disabled) and rejected ones red
+    pub fn ansi(&self) -> String {
+        const RESET: &str = "\x1b[0m";
+        let mut text = String::new();
+        let mut pos = 0;
+        for annotation in self.annotations.iter() {
+            let color = match (annotation.verdict, annotation.enabled) {
+                (Ok(()), true) => "\x1b[32m",
+                (Ok(()), false) => "\x1b[33m",
+                (Err(_), _) => "\x1b[31m",
+            };
+            text.push_str(&self.input[pos..annotation.span.start]);
+            text.push_str(color);
+            text.push_str(&self.input[annotation.span.clone()]);
+            text.push_str(RESET);
+            pos = annotation.span.end;
+        }
+        text.push_str(&self.input[pos..]);
+        text
+    }
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for annotation in self.annotations.iter() {
+            let verdict = match annotation.verdict {
+                Ok(()) => "accepted".to_string(),
+                Err(reason) => format!("rejected: {}", reason),
+            };
+            let state = if annotation.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            write!(
+                f,
+                "{:>6}  {:<16}  {:<30}  {}",
+                annotation.span.start,
+                &self.input[annotation.span.clone()],
+                verdict,
+                state
+            )?;
+            f.write_char('\n')?;
+        }
+        Ok(())
     }
 }
 
+pub fn part_one(input: &str) -> isize {
+    Machine::run(&[MUL], input).unwrap().accumulator
+}
+
+pub fn part_two(input: &str) -> isize {
+    Machine::run(&[MUL, DO, DONT], input).unwrap().accumulator
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -106,6 +484,154 @@ mod tests {
         assert_eq!(48, part_two(input));
     }
 
+    #[test]
+    fn test_lexer() {
+        let input = "xmul(2,4)%do()mul(1,mul(3,7))mul(1234,5)don't()";
+        let definitions = [MUL, DO, DONT];
+        let tokens: Vec<_> = Lexer::new(input, &definitions)
+            .map(|token| {
+                let instruction = token.instruction;
+                (
+                    instruction.definition.name,
+                    instruction.operands,
+                    token.span,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("mul", vec![2, 4], 1..9),
+                ("do", vec![], 10..14),
+                ("mul", vec![3, 7], 20..28),
+                ("don't", vec![], 40..47),
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_variants() {
+        let input = "add(1,2)don't()mul(2,3)don't()do()mul(4,5)do()mul(1000,1)";
+        let wide_mul = Definition {
+            max_digits: 4,
+            ..MUL
+        };
+        let machine = Machine::run(&[ADD, wide_mul, NESTED_DO, NESTED_DONT], input).unwrap();
+        assert_eq!(1003, machine.accumulator);
+        assert!(machine.is_enabled());
+        assert_eq!(
+            23,
+            Machine::run(&[ADD, MUL, DO, DONT], input)
+                .unwrap()
+                .accumulator
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let widest = Definition {
+            max_digits: 20,
+            ..MUL
+        };
+        let input = "mul(99999999999999999999,1)mul(2,3)";
+        let lexemes: Vec<_> = Lexer::new(input, &[widest])
+            .lexemes()
+            .map(|lexeme| match lexeme {
+                Lexeme::Rejected(rejection) => Err(rejection.reason),
+                Lexeme::Accepted(token) => Ok(token.instruction.operands),
+            })
+            .collect();
+        assert_eq!(vec![Err(Reason::Overflow), Ok(vec![2, 3])], lexemes);
+        // both operands fit, their product does not
+        let wide = [Definition {
+            max_digits: 10,
+            ..MUL
+        }];
+        let input = "mul(2,3)mul(9999999999,9999999999)";
+        assert_eq!(Some(Overflow { pos: 8 }), Machine::run(&wide, input).err());
+        let report = Report::annotate(&wide, input);
+        let verdicts: Vec<_> = report.annotations.iter().map(|a| a.verdict).collect();
+        assert_eq!(vec![Ok(()), Err(Reason::Overflow)], verdicts);
+    }
+
+    #[test]
+    fn test_overlapping_names() {
+        // `do()` starts inside the broken `add(` candidate
+        let input = "don't()addo()add(1,2)";
+        assert_eq!(
+            3,
+            Machine::run(&[ADD, DO, DONT], input).unwrap().accumulator
+        );
+        let report = Report::annotate(&[ADD, DO, DONT], input);
+        let names: Vec<_> = report.annotations.iter().map(|a| a.name).collect();
+        assert_eq!(vec!["don't", "do", "add"], names);
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let definitions = [MUL, DO, DONT];
+        let report = Report::annotate(&definitions, input);
+        let annotations: Vec<_> = report
+            .annotations
+            .iter()
+            .map(|a| (a.span.start, a.verdict, a.enabled))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, Ok(()), true),
+                (20, Ok(()), true),
+                (28, Ok(()), false),
+                (37, Err(Reason::Unexpected(']')), false),
+                (48, Ok(()), false),
+                (59, Ok(()), false),
+                (64, Ok(()), true),
+            ],
+            annotations
+        );
+        let text = report.to_string();
+        assert_eq!(
+            "    37  mul(32,64         rejected: unexpected ']'        disabled",
+            text.lines().nth(3).unwrap()
+        );
+        assert!(report
+            .ansi()
+            .starts_with("x\x1b[32mmul(2,4)\x1b[0m&mul[3,7]"));
+    }
+
+    #[test]
+    fn test_reasons() {
+        let reasons: Vec<_> = [
+            "mul(1234,5)",
+            "mul(,5)",
+            "mul(5)",
+            "mul(1,2,3)",
+            "do(1)",
+            "mul(1,2",
+        ]
+        .into_iter()
+        .map(|input| {
+            let lexeme = Lexer::new(input, &[MUL, DO]).lexemes().next().unwrap();
+            match lexeme {
+                Lexeme::Rejected(rejection) => rejection.reason,
+                Lexeme::Accepted(_) => panic!("{} accepted", input),
+            }
+        })
+        .collect();
+        use Reason::*;
+        assert_eq!(
+            vec![
+                TooManyDigits,
+                EmptyOperand,
+                MissingComma,
+                TooManyOperands,
+                TooManyOperands,
+                Unterminated
+            ],
+            reasons
+        );
+    }
+
     #[test]
     fn test_split() {
         let input = "mul(123)";
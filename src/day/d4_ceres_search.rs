@@ -1,70 +1,207 @@
-pub fn part_one(input: &str) -> usize {
-    let mut counter = 0;
-    let rows: Vec<_> = input.lines().collect();
-    for (r, row) in rows.iter().enumerate() {
-        for (c, ch) in row.chars().enumerate() {
-            if ch == 'X' {
-                let r = r as isize;
-                let c = c as isize;
-                #[allow(clippy::identity_op)] //for optics
-                [
-                    [(r + 1, c + 0), (r + 2, c + 0), (r + 3, c + 0)],
-                    [(r - 1, c + 0), (r - 2, c + 0), (r - 3, c + 0)],
-                    [(r + 0, c + 1), (r + 0, c + 2), (r + 0, c + 3)],
-                    [(r + 0, c - 1), (r + 0, c - 2), (r + 0, c - 3)],
-                    [(r + 1, c + 1), (r + 2, c + 2), (r + 3, c + 3)],
-                    [(r - 1, c - 1), (r - 2, c - 2), (r - 3, c - 3)],
-                    [(r + 1, c - 1), (r + 2, c - 2), (r + 3, c - 3)],
-                    [(r - 1, c + 1), (r - 2, c + 2), (r - 3, c + 3)],
-                ]
-                .into_iter()
-                .for_each(|idx| {
-                    let mut iter = idx.iter().map(|(r, c)| {
-                        if *r < 0 || *c < 0 {
-                            return None;
-                        }
-                        rows.get(*r as usize)
-                            .and_then(|row| row.chars().nth(*c as usize))
-                    });
-                    let m = iter.next().unwrap();
-                    let a = iter.next().unwrap();
-                    let s = iter.next().unwrap();
-                    if let (Some('M'), Some('A'), Some('S')) = (m, a, s) {
-                        counter += 1
-                    }
+use crate::util::position::Position;
+
+/// letters of the puzzle as bytes, one row after the other, every row as long as the first
+pub struct Grid {
+    cells: Vec<u8>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Grid {
+    pub fn parse(input: &str) -> Self {
+        let lines: Vec<&str> = input.lines().collect();
+        let cols = lines.first().map_or(0, |line| line.len());
+        Grid {
+            cells: lines.iter().flat_map(|line| line.bytes()).collect(),
+            rows: lines.len(),
+            cols,
+        }
+    }
+
+    fn get(&self, row: usize, col: usize) -> u8 {
+        self.cells[row * self.cols + col]
+    }
+}
+
+/// letters relative to the top left corner of a pattern, wildcards are left out
+pub struct Template {
+    cells: Vec<(isize, isize, u8)>,
+}
+
+impl Template {
+    /// a word read from left to right
+    pub fn word(word: &str) -> Self {
+        let cells = word
+            .bytes()
+            .enumerate()
+            .map(|(col, letter)| (0, col as isize, letter))
+            .collect();
+        Template { cells }
+    }
+
+    /// rows of a 2D pattern, `wildcard` matches any letter
+    ///
+    /// matched byte by byte like the grid, rows may be shorter than others and match anything
+    /// past their end
+    pub fn grid(pattern: &str, wildcard: u8) -> Self {
+        let mut cells = Vec::new();
+        for (row, line) in pattern.lines().enumerate() {
+            for (col, letter) in line.bytes().enumerate() {
+                if letter != wildcard {
+                    cells.push((row as isize, col as isize, letter));
+                }
+            }
+        }
+        Template { cells }
+    }
+}
+
+/// rotation clockwise in steps of 45 degrees, mirrored left to right before rotating
+///
+/// odd steps put neighbouring letters of a row on a diagonal, which only keeps the shape of words
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Orientation {
+    pub eighth_turns: u8,
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// the 8 directions a word can be read in
+    pub fn directions() -> Vec<Orientation> {
+        (0..8)
+            .map(|eighth_turns| Orientation {
+                eighth_turns,
+                mirrored: false,
+            })
+            .collect()
+    }
+
+    /// rotations and reflections of a 2D pattern
+    pub fn symmetries() -> Vec<Orientation> {
+        let mut orientations = Vec::new();
+        for mirrored in [false, true] {
+            for eighth_turns in [0, 2, 4, 6] {
+                orientations.push(Orientation {
+                    eighth_turns,
+                    mirrored,
                 });
             }
         }
+        orientations
+    }
+
+    fn apply(&self, (mut row, mut col): (isize, isize)) -> (isize, isize) {
+        if self.mirrored {
+            col = -col;
+        }
+        for _ in 0..self.eighth_turns / 2 {
+            (row, col) = (col, -row);
+        }
+        if self.eighth_turns % 2 == 1 {
+            (row, col) = (row + col, col - row);
+        }
+        (row, col)
     }
-    counter
 }
 
-pub fn part_two(input: &str) -> usize {
-    let rows: Vec<_> = input.lines().collect();
-    let mut counter = 0;
-    let row_cnt = rows.len();
-    let col_cnt = rows[0].chars().count();
-    for (r, row) in rows.iter().enumerate().skip(1).take(row_cnt - 2) {
-        for (c, ch) in row.chars().enumerate().skip(1).take(col_cnt - 2) {
-            if ch == 'A' {
-                let neighbours = [
-                    (r - 1, c - 1),
-                    (r + 1, c + 1),
-                    (r - 1, c + 1),
-                    (r + 1, c - 1),
-                ]
-                .map(|(r, c)| rows[r].chars().nth(c).unwrap());
-                match neighbours {
-                    ['M', 'S', 'M', 'S']
-                    | ['M', 'S', 'S', 'M']
-                    | ['S', 'M', 'M', 'S']
-                    | ['S', 'M', 'S', 'M'] => counter += 1,
-                    _ => {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// top left corner of the oriented pattern
+    pub pos: Position,
+    pub orientation: Orientation,
+}
+
+/// oriented copy of a template, shifted so its top left corner is at the origin
+struct Variant {
+    orientation: Orientation,
+    cells: Vec<(usize, usize, u8)>,
+    rows: usize,
+    cols: usize,
+}
+
+pub struct Search {
+    variants: Vec<Variant>,
+}
+
+impl Search {
+    /// orientations mapping the template onto the same cells are only searched once
+    pub fn new(template: &Template, orientations: &[Orientation]) -> Self {
+        let mut variants: Vec<Variant> = Vec::new();
+        for &orientation in orientations {
+            let cells: Vec<(isize, isize, u8)> = template
+                .cells
+                .iter()
+                .map(|&(row, col, letter)| {
+                    let (row, col) = orientation.apply((row, col));
+                    (row, col, letter)
+                })
+                .collect();
+            let min_row = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+            let min_col = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+            let mut cells: Vec<(usize, usize, u8)> = cells
+                .into_iter()
+                .map(|(row, col, letter)| {
+                    ((row - min_row) as usize, (col - min_col) as usize, letter)
+                })
+                .collect();
+            cells.sort();
+            if variants.iter().any(|variant| variant.cells == cells) {
+                continue;
+            }
+            variants.push(Variant {
+                orientation,
+                rows: cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0),
+                cols: cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0),
+                cells,
+            });
+        }
+        Search { variants }
+    }
+
+    /// `XMAS` in any direction
+    pub fn xmas() -> Self {
+        Search::new(&Template::word("XMAS"), &Orientation::directions())
+    }
+
+    /// two diagonal `MAS` crossing in their `A`
+    pub fn x_mas() -> Self {
+        let template = Template::grid("M.S\n.A.\nM.S", b'.');
+        Search::new(&template, &Orientation::symmetries())
+    }
+
+    pub fn find(&self, grid: &Grid) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for variant in self.variants.iter() {
+            if variant.rows > grid.rows || variant.cols > grid.cols {
+                continue;
+            }
+            for row in 0..=grid.rows - variant.rows {
+                for col in 0..=grid.cols - variant.cols {
+                    let found = variant
+                        .cells
+                        .iter()
+                        .all(|&(r, c, letter)| grid.get(row + r, col + c) == letter);
+                    if found {
+                        matches.push(Match {
+                            pos: Position(row, col),
+                            orientation: variant.orientation,
+                        });
+                    }
                 }
             }
         }
+        matches
     }
-    counter
+}
+
+pub fn part_one(input: &str) -> usize {
+    let grid = Grid::parse(input);
+    Search::xmas().find(&grid).len()
+}
+
+pub fn part_two(input: &str) -> usize {
+    let grid = Grid::parse(input);
+    Search::x_mas().find(&grid).len()
 }
 
 #[cfg(test)]
@@ -87,4 +224,43 @@ MXMXAXMASX";
         assert_eq!(18, part_one(input));
         assert_eq!(9, part_two(input));
     }
+
+    #[test]
+    fn test_orientations() {
+        let input = "..X...
+.SAMX.
+.A..A.
+XMAS.S
+.X....";
+        let grid = Grid::parse(input);
+        let matches = Search::xmas().find(&grid);
+        assert_eq!(4, matches.len());
+        // read upwards from the X in the last row
+        assert!(matches.contains(&Match {
+            pos: Position(1, 1),
+            orientation: Orientation {
+                eighth_turns: 6,
+                mirrored: false
+            }
+        }));
+        assert_eq!(4, Search::x_mas().variants.len());
+        // an L shape has no symmetry, every orientation is distinct
+        let template = Template::grid("A.\nBC", b'.');
+        let search = Search::new(&template, &Orientation::symmetries());
+        assert_eq!(8, search.variants.len());
+        assert_eq!(1, search.find(&Grid::parse("CB\n.A")).len());
+        // multi-byte letters are compared as the bytes the grid holds
+        let search = Search::new(
+            &Template::grid("é\nA", b'.'),
+            &Orientation::symmetries()[..1],
+        );
+        assert_eq!(1, search.find(&Grid::parse("é\nA.")).len());
+        assert_eq!(0, search.find(&Grid::parse("è\nA.")).len());
+        // the short second row leaves its missing cells open
+        let search = Search::new(
+            &Template::grid("AB\nC", b'.'),
+            &Orientation::symmetries()[..1],
+        );
+        assert_eq!(1, search.find(&Grid::parse("AB\nCx")).len());
+    }
 }
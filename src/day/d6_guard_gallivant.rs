@@ -1,10 +1,9 @@
 use std::{
     collections::HashSet,
     fmt::{Display, Write},
-    thread,
 };
 
-use crate::util::{position::*, vec2d::Vec2D};
+use crate::util::{parallel, position::*, vec2d::Vec2D};
 use Direction::*;
 use FieldType::*;
 
@@ -145,27 +144,25 @@ pub fn part_one(input: &str) -> usize {
 
 //TODO is there a faster way? this takes 5s in release and 60s in debug
 pub fn part_two(input: &str) -> usize {
-    let mut threads = Vec::new();
     let original = Guard::parse(input);
-    for (idx_row, row) in original.map.0.iter().enumerate() {
-        for (idx_col, field) in row.iter().enumerate() {
-            if original.position == Position(idx_row, idx_col) {
-                continue;
-            }
-            if matches!(field.typ, Obstacle) {
-                continue;
-            }
-            let mut guard = original.clone();
-            guard.map.0[idx_row][idx_col].typ = Obstacle;
-            let handle = thread::spawn(move || guard.walk());
-            threads.push(handle);
-        }
-    }
-    threads
-        .into_iter()
-        .map(|handle| handle.join().unwrap())
-        .filter(|end| matches!(end, End::Loop))
-        .count()
+    let candidates: Vec<Position> = original
+        .map
+        .iter_pos()
+        .filter(|&pos| pos != original.position)
+        .filter(|&pos| matches!(original.map.get(pos).unwrap().typ, Free))
+        .collect();
+    parallel::map_chunks(&candidates, |chunk| {
+        chunk
+            .iter()
+            .filter(|&&pos| {
+                let mut guard = original.clone();
+                guard.map.get_mut(pos).unwrap().typ = Obstacle;
+                matches!(guard.walk(), End::Loop)
+            })
+            .count()
+    })
+    .into_iter()
+    .sum()
 }
 
 #[cfg(test)]
@@ -1,10 +1,368 @@
-struct Equation {
-    result: usize,
-    args: Vec<usize>,
+use std::{collections::HashMap, fmt::Display};
+
+/// binary operator, equations are evaluated left to right
+pub trait Operator {
+    fn symbol(&self) -> &'static str;
+    fn apply(&self, left: u128, right: u128) -> Option<u128>;
+    /// the left operand giving `result` together with `right`
+    fn undo(&self, result: u128, right: u128) -> Option<u128>;
+    /// every left operand gives `result` together with `right`, `undo` is not asked then
+    fn absorbs(&self, _result: u128, _right: u128) -> bool {
+        false
+    }
+}
+
+pub struct Add;
+pub struct Multiply;
+pub struct Concat;
+/// intermediate results must not become negative
+pub struct Subtract;
+pub struct Xor;
+
+impl Operator for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn apply(&self, left: u128, right: u128) -> Option<u128> {
+        left.checked_add(right)
+    }
+
+    fn undo(&self, result: u128, right: u128) -> Option<u128> {
+        result.checked_sub(right)
+    }
+}
+
+impl Operator for Multiply {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn apply(&self, left: u128, right: u128) -> Option<u128> {
+        left.checked_mul(right)
+    }
+
+    fn undo(&self, result: u128, right: u128) -> Option<u128> {
+        match right {
+            0 => None,
+            right if result.is_multiple_of(right) => Some(result / right),
+            _ => None,
+        }
+    }
+
+    fn absorbs(&self, result: u128, right: u128) -> bool {
+        result == 0 && right == 0
+    }
+}
+
+/// power of ten with as many zeros as `n` has digits
+fn digit_shift(n: u128) -> Option<u128> {
+    let mut shift: u128 = 10;
+    while shift <= n {
+        shift = shift.checked_mul(10)?;
+    }
+    Some(shift)
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+
+    fn apply(&self, left: u128, right: u128) -> Option<u128> {
+        left.checked_mul(digit_shift(right)?)?.checked_add(right)
+    }
+
+    fn undo(&self, result: u128, right: u128) -> Option<u128> {
+        let shift = digit_shift(right)?;
+        (result % shift == right).then_some(result / shift)
+    }
+}
+
+impl Operator for Subtract {
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn apply(&self, left: u128, right: u128) -> Option<u128> {
+        left.checked_sub(right)
+    }
+
+    fn undo(&self, result: u128, right: u128) -> Option<u128> {
+        result.checked_add(right)
+    }
+}
+
+impl Operator for Xor {
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn apply(&self, left: u128, right: u128) -> Option<u128> {
+        Some(left ^ right)
+    }
+
+    fn undo(&self, result: u128, right: u128) -> Option<u128> {
+        Some(result ^ right)
+    }
+}
+
+pub const ADD_MUL: [&dyn Operator; 2] = [&Add, &Multiply];
+pub const ADD_MUL_CONCAT: [&dyn Operator; 3] = [&Add, &Multiply, &Concat];
+
+pub struct Equation {
+    pub result: u128,
+    pub args: Vec<u128>,
+}
+
+/// operators which make an equation true
+pub struct Witness<'a> {
+    args: &'a [u128],
+    operators: Vec<&'a dyn Operator>,
+}
+
+impl Display for Witness<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.args[0])?;
+        for (operator, arg) in self.operators.iter().zip(self.args[1..].iter()) {
+            write!(f, " {} {}", operator.symbol(), arg)?;
+        }
+        Ok(())
+    }
+}
+
+impl Witness<'_> {
+    /// evaluates the expression left to right
+    pub fn evaluate(&self) -> Option<u128> {
+        let mut value = self.args[0];
+        for (operator, &arg) in self.operators.iter().zip(self.args[1..].iter()) {
+            value = operator.apply(value, arg)?;
+        }
+        Some(value)
+    }
+}
+
+/// works backwards from the result, `chosen[i]` is the operator in front of `args[i + 1]`
+fn backtrack(
+    result: u128,
+    args: &[u128],
+    operators: &[&dyn Operator],
+    chosen: &mut [usize],
+) -> bool {
+    let Some((&last, rest)) = args.split_last() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return result == last;
+    }
+    for (idx, operator) in operators.iter().enumerate() {
+        chosen[rest.len() - 1] = idx;
+        let found = if operator.absorbs(result, last) {
+            // any left operand will do, the arguments in front only have to evaluate
+            evaluate_any(rest[0], &rest[1..], operators, chosen)
+        } else if let Some(left) = operator.undo(result, last) {
+            backtrack(left, rest, operators, chosen)
+        } else {
+            false
+        };
+        if found {
+            return true;
+        }
+    }
+    false
+}
+
+/// works forwards from `value` until some operators evaluate all of `args` without overflow
+fn evaluate_any(
+    value: u128,
+    args: &[u128],
+    operators: &[&dyn Operator],
+    chosen: &mut [usize],
+) -> bool {
+    let Some((&first, rest)) = args.split_first() else {
+        return true;
+    };
+    for (idx, operator) in operators.iter().enumerate() {
+        if let Some(next) = operator.apply(value, first) {
+            chosen[0] = idx;
+            if evaluate_any(next, rest, operators, &mut chosen[1..]) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// memoized counts of operator assignments, `None` once a count does not fit into `u128`
+struct Counter<'a> {
+    args: &'a [u128],
+    operators: &'a [&'a dyn Operator],
+    /// assignments turning the first `len` arguments into `result`, by `(len, result)`
+    backward: HashMap<(usize, u128), Option<u128>>,
+    /// assignments evaluating the first `end` arguments from `value` at `pos`, by `(pos, end, value)`
+    forward: HashMap<(usize, usize, u128), Option<u128>>,
+}
+
+impl Counter<'_> {
+    fn backward(&mut self, len: usize, result: u128) -> Option<u128> {
+        if len == 1 {
+            return Some((result == self.args[0]) as u128);
+        }
+        if let Some(&total) = self.backward.get(&(len, result)) {
+            return total;
+        }
+        let right = self.args[len - 1];
+        let mut total: Option<u128> = Some(0);
+        for operator in self.operators {
+            let found = if operator.absorbs(result, right) {
+                self.forward(1, len - 1, self.args[0])
+            } else if let Some(left) = operator.undo(result, right) {
+                self.backward(len - 1, left)
+            } else {
+                continue;
+            };
+            total = total
+                .zip(found)
+                .and_then(|(total, found)| total.checked_add(found));
+        }
+        self.backward.insert((len, result), total);
+        total
+    }
+
+    fn forward(&mut self, pos: usize, end: usize, value: u128) -> Option<u128> {
+        if pos == end {
+            return Some(1);
+        }
+        if let Some(&total) = self.forward.get(&(pos, end, value)) {
+            return total;
+        }
+        let mut total: Option<u128> = Some(0);
+        for operator in self.operators {
+            if let Some(next) = operator.apply(value, self.args[pos]) {
+                let found = self.forward(pos + 1, end, next);
+                total = total
+                    .zip(found)
+                    .and_then(|(total, found)| total.checked_add(found));
+            }
+        }
+        self.forward.insert((pos, end, value), total);
+        total
+    }
+}
+
+/// depth first search state, the operator in front of `args[i]` goes into `chosen[i - 1]`
+#[derive(Clone, Copy)]
+enum Frame {
+    /// the first `len` arguments have to evaluate to `result`
+    Backward {
+        len: usize,
+        result: u128,
+        next_op: usize,
+    },
+    /// the first `pos` arguments evaluate to `value`, any value of the first `end` will do
+    Forward {
+        pos: usize,
+        end: usize,
+        value: u128,
+        next_op: usize,
+    },
+}
+
+/// lazy depth first search over all satisfying operator assignments
+pub struct Witnesses<'a> {
+    args: &'a [u128],
+    operators: &'a [&'a dyn Operator],
+    frames: Vec<Frame>,
+    chosen: Vec<usize>,
+}
+
+impl<'a> Witnesses<'a> {
+    fn witness(&self) -> Witness<'a> {
+        Witness {
+            args: self.args,
+            operators: self.chosen.iter().map(|&idx| self.operators[idx]).collect(),
+        }
+    }
+}
+
+impl<'a> Iterator for Witnesses<'a> {
+    type Item = Witness<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(frame) = self.frames.pop() {
+            match frame {
+                Frame::Backward {
+                    len,
+                    result,
+                    next_op,
+                } => {
+                    if len == 1 {
+                        if result == self.args[0] {
+                            return Some(self.witness());
+                        }
+                        continue;
+                    }
+                    let Some(operator) = self.operators.get(next_op) else {
+                        continue;
+                    };
+                    self.frames.push(Frame::Backward {
+                        len,
+                        result,
+                        next_op: next_op + 1,
+                    });
+                    self.chosen[len - 2] = next_op;
+                    let right = self.args[len - 1];
+                    if operator.absorbs(result, right) {
+                        self.frames.push(Frame::Forward {
+                            pos: 1,
+                            end: len - 1,
+                            value: self.args[0],
+                            next_op: 0,
+                        });
+                    } else if let Some(left) = operator.undo(result, right) {
+                        self.frames.push(Frame::Backward {
+                            len: len - 1,
+                            result: left,
+                            next_op: 0,
+                        });
+                    }
+                }
+                Frame::Forward {
+                    pos,
+                    end,
+                    value,
+                    next_op,
+                } => {
+                    if pos == end {
+                        return Some(self.witness());
+                    }
+                    let Some(operator) = self.operators.get(next_op) else {
+                        continue;
+                    };
+                    self.frames.push(Frame::Forward {
+                        pos,
+                        end,
+                        value,
+                        next_op: next_op + 1,
+                    });
+                    self.chosen[pos - 1] = next_op;
+                    if let Some(next) = operator.apply(value, self.args[pos]) {
+                        self.frames.push(Frame::Forward {
+                            pos: pos + 1,
+                            end,
+                            value: next,
+                            next_op: 0,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
 }
 
 impl Equation {
-    fn parse(input: &str) -> Vec<Equation> {
+    pub fn parse(input: &str) -> Vec<Equation> {
         let mut equations = Vec::new();
         for line in input.lines() {
             let (result, args) = line.split_once(": ").unwrap();
@@ -18,66 +376,64 @@ impl Equation {
         equations
     }
 
-    fn possible_true(&self, concat: bool) -> bool {
-        if self.args.len() == 1 {
-            return self.result == self.args[0];
+    pub fn solve<'a>(&'a self, operators: &[&'a dyn Operator]) -> Option<Witness<'a>> {
+        let mut chosen = vec![0; self.args.len().saturating_sub(1)];
+        if !backtrack(self.result, &self.args, operators, &mut chosen) {
+            return None;
         }
-        let mut args = self.args.clone();
-        let arg = args.pop().unwrap();
-        let args = args;
+        Some(Witness {
+            args: &self.args,
+            operators: chosen.into_iter().map(|idx| operators[idx]).collect(),
+        })
+    }
 
-        if self.result % arg == 0 {
-            let result = self.result / arg;
-            let eq = Equation {
-                result,
-                args: args.clone(),
-            };
-            if eq.possible_true(concat) {
-                return true;
-            }
+    /// number of distinct operator assignments which make the equation true, `None` on overflow
+    pub fn count(&self, operators: &[&dyn Operator]) -> Option<u128> {
+        if self.args.is_empty() {
+            return Some(0);
         }
-        if concat {
-            let mut result_str = self.result.to_string();
-            let arg_str = arg.to_string();
-            if result_str.ends_with(&arg_str) {
-                for _ in 0..arg_str.chars().count() {
-                    result_str.pop().unwrap();
-                }
-                if let Ok(result) = result_str.parse() {
-                    let eq = Equation {
-                        result,
-                        args: args.clone(),
-                    };
-                    if eq.possible_true(concat) {
-                        return true;
-                    }
-                }
-            }
-        }
-        if let Some(result) = self.result.checked_sub(arg) {
-            let eq = Equation { result, args };
-            if eq.possible_true(concat) {
-                return true;
-            }
+        let mut counter = Counter {
+            args: &self.args,
+            operators,
+            backward: HashMap::new(),
+            forward: HashMap::new(),
+        };
+        counter.backward(self.args.len(), self.result)
+    }
+
+    pub fn witnesses<'a>(&'a self, operators: &'a [&'a dyn Operator]) -> Witnesses<'a> {
+        let frames = if self.args.is_empty() {
+            Vec::new()
+        } else {
+            vec![Frame::Backward {
+                len: self.args.len(),
+                result: self.result,
+                next_op: 0,
+            }]
+        };
+        Witnesses {
+            args: &self.args,
+            operators,
+            frames,
+            chosen: vec![0; self.args.len().saturating_sub(1)],
         }
-        false
     }
 }
 
-pub fn part_one(input: &str) -> usize {
+pub fn part_one(input: &str) -> u128 {
     let equations = Equation::parse(input);
     equations
-        .into_iter()
-        .filter(|eq| eq.possible_true(false))
+        .iter()
+        .filter(|eq| eq.solve(&ADD_MUL).is_some())
         .map(|eq| eq.result)
         .sum()
 }
 
-pub fn part_two(input: &str) -> usize {
+pub fn part_two(input: &str) -> u128 {
     let equations = Equation::parse(input);
     equations
-        .into_iter()
-        .filter(|eq| eq.possible_true(true))
+        .iter()
+        .filter(|eq| eq.solve(&ADD_MUL_CONCAT).is_some())
         .map(|eq| eq.result)
         .sum()
 }
@@ -106,4 +462,75 @@ mod tests {
     fn test_concat() {
         assert_eq!(192, part_two("192: 17 8 14"))
     }
+
+    #[test]
+    fn test_witness() {
+        let equation = &Equation::parse("7290: 6 8 6 15")[0];
+        assert!(equation.solve(&ADD_MUL).is_none());
+        let witness = equation.solve(&ADD_MUL_CONCAT).unwrap();
+        assert_eq!("6 * 8 || 6 * 15", witness.to_string());
+        assert_eq!(Some(7290), witness.evaluate());
+        let equation = &Equation::parse("11: 12 4 3")[0];
+        let operators: [&dyn Operator; 2] = [&Subtract, &Xor];
+        let witness = equation.solve(&operators).unwrap();
+        assert_eq!("12 - 4 ^ 3", witness.to_string());
+        assert_eq!(Some(11), witness.evaluate());
+    }
+
+    #[test]
+    fn test_zero_operand() {
+        let equation = &Equation::parse("0: 5 0")[0];
+        assert_eq!("5 * 0", equation.solve(&ADD_MUL).unwrap().to_string());
+        // adding the zero leaves 7, only multiplying by it works
+        let equation = &Equation::parse("0: 3 4 0")[0];
+        let witness = equation.solve(&ADD_MUL).unwrap();
+        assert_eq!("3 + 4 * 0", witness.to_string());
+        assert_eq!(Some(0), witness.evaluate());
+        assert!(Equation::parse("1: 5 0")[0].solve(&ADD_MUL).is_none());
+        let equation = &Equation::parse("0: 5 0")[0];
+        assert_eq!(Some(1), equation.count(&ADD_MUL));
+        assert_eq!(1, equation.witnesses(&ADD_MUL).count());
+        let equation = &Equation::parse("0: 3 4 0")[0];
+        assert_eq!(Some(2), equation.count(&ADD_MUL));
+        let witnesses: Vec<String> = equation
+            .witnesses(&ADD_MUL)
+            .map(|witness| witness.to_string())
+            .collect();
+        assert_eq!(vec!["3 + 4 * 0", "3 * 4 * 0"], witnesses);
+    }
+
+    #[test]
+    fn test_count_overflow() {
+        // two interchangeable operators double the count with every argument
+        let operators: [&dyn Operator; 2] = [&Xor, &Xor];
+        let zeros = |n: usize| Equation {
+            result: 0,
+            args: vec![0; n],
+        };
+        assert_eq!(Some(1 << 127), zeros(128).count(&operators));
+        assert_eq!(None, zeros(129).count(&operators));
+    }
+
+    #[test]
+    fn test_count() {
+        let equation = &Equation::parse("3267: 81 40 27")[0];
+        assert_eq!(Some(2), equation.count(&ADD_MUL));
+        let witnesses: Vec<String> = equation
+            .witnesses(&ADD_MUL)
+            .map(|witness| witness.to_string())
+            .collect();
+        assert_eq!(vec!["81 * 40 + 27", "81 + 40 * 27"], witnesses);
+        let equation = &Equation::parse("2: 1 1 1 1")[0];
+        assert_eq!(Some(3), equation.count(&ADD_MUL));
+        assert_eq!(3, equation.witnesses(&ADD_MUL).count());
+        assert_eq!(
+            Some(0),
+            Equation::parse("83: 17 5")[0].count(&ADD_MUL_CONCAT)
+        );
+        // far beyond u64 once concatenated
+        let equation = &Equation::parse("123456789012345678901234: 123456789012 345678901234")[0];
+        assert_eq!(Some(1), equation.count(&ADD_MUL_CONCAT));
+        let witness = equation.witnesses(&ADD_MUL_CONCAT).next().unwrap();
+        assert_eq!(Some(equation.result), witness.evaluate());
+    }
 }
@@ -1,176 +1,202 @@
-use crate::util::position::*;
-use crate::util::vec2d::Vec2D;
-use std::collections::HashMap;
-use std::fmt::{Display, Write};
-use std::vec;
-
-enum FieldType {
-    Empty,
-    Antenna(char),
-}
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-struct Field {
-    typ: FieldType,
-    antinote: bool,
-}
+/// signed, so antinodes outside of the map can be represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point(pub i64, pub i64);
 
-impl Field {
-    fn new(typ: FieldType) -> Self {
-        Field {
-            typ,
-            antinote: false,
-        }
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
     }
 }
 
-impl Position {
-    fn antidotes_pos(&self, other: Position) -> Vec<Position> {
-        let mut antinodes = Vec::new();
-        let diff_x = other.0 as isize - self.0 as isize;
-        let diff_y = other.1 as isize - self.1 as isize;
-        let anti_x = other.0 as isize + diff_x;
-        let anti_y = other.1 as isize + diff_y;
-        if !anti_x.is_negative() && !anti_y.is_negative() {
-            antinodes.push(Position(anti_x as usize, anti_y as usize));
-        }
-        let anti_x = self.0 as isize - diff_x;
-        let anti_y = self.1 as isize - diff_y;
-        if !anti_x.is_negative() && !anti_y.is_negative() {
-            antinodes.push(Position(anti_x as usize, anti_y as usize));
+/// antennas by frequency, without materialising the empty fields
+pub struct Map {
+    antennas: HashMap<char, Vec<Point>>,
+    rows: i64,
+    cols: i64,
+}
+
+impl Map {
+    pub fn new(rows: i64, cols: i64, antennas: HashMap<char, Vec<Point>>) -> Self {
+        Map {
+            antennas,
+            rows,
+            cols,
         }
-        antinodes
     }
 
-    fn antidotes_pos_resonant(&self, other: Position, end: Position) -> Vec<Position> {
-        let in_range = |x: isize, y: isize| {
-            if !x.is_negative() && !y.is_negative() && x <= end.0 as isize && y <= end.1 as isize {
-                Some(Position(x as usize, y as usize))
-            } else {
-                None
-            }
-        };
-        let mut antinodes = vec![*self, other];
-        let diff_x = other.0 as isize - self.0 as isize;
-        let diff_y = other.1 as isize - self.1 as isize;
-        let mut pos = other;
-        loop {
-            let anti_x = pos.0 as isize + diff_x;
-            let anti_y = pos.1 as isize + diff_y;
-            if let Some(new_pos) = in_range(anti_x, anti_y) {
-                antinodes.push(new_pos);
-                pos = new_pos;
-            } else {
-                break;
-            }
-        }
-        pos = *self;
-        loop {
-            let anti_x = pos.0 as isize - diff_x;
-            let anti_y = pos.1 as isize - diff_y;
-            if let Some(new_pos) = in_range(anti_x, anti_y) {
-                antinodes.push(new_pos);
-                pos = new_pos;
-            } else {
-                break;
+    pub fn parse(input: &str) -> Self {
+        let mut antennas: HashMap<char, Vec<Point>> = HashMap::new();
+        let mut rows = 0;
+        let mut cols = 0;
+        for (idx_row, line) in input.lines().enumerate() {
+            for (idx_col, c) in line.chars().enumerate() {
+                if c != '.' {
+                    let pos = Point(idx_row as i64, idx_col as i64);
+                    antennas.entry(c).or_default().push(pos);
+                }
             }
+            rows += 1;
+            cols = line.len() as i64;
         }
-        antinodes
+        Map::new(rows, cols, antennas)
     }
-}
 
-struct Map {
-    map: Vec2D<Field>,
-    antennas: HashMap<char, Vec<Position>>,
-    end: Position,
-}
+    fn contains(&self, pos: Point) -> bool {
+        (0..self.rows).contains(&pos.0) && (0..self.cols).contains(&pos.1)
+    }
 
-impl Map {
-    fn parse(input: &str) -> Self {
-        let mut map = Vec::new();
-        let mut antennas: HashMap<char, Vec<Position>> = HashMap::new();
-        for (idx_row, line) in input.lines().enumerate() {
-            let mut row = Vec::new();
-            for (idx_col, c) in line.chars().enumerate() {
-                let typ = match c {
-                    '.' => FieldType::Empty,
-                    c => FieldType::Antenna(c),
-                };
-                if let FieldType::Antenna(c) = typ {
-                    let pos = Position(idx_row, idx_col);
-                    if let Some(positions) = antennas.get_mut(&c) {
-                        positions.push(pos);
-                    } else {
-                        let old = antennas.insert(c, vec![pos]);
-                        assert!(old.is_none())
+    /// antinodes of one pair, a ratio whose points are off the grid adds none
+    fn pair_antinodes(
+        &self,
+        a: Point,
+        b: Point,
+        distance: Distance,
+        antinodes: &mut HashSet<Point>,
+    ) {
+        // antennas sharing a position span no line
+        if a == b {
+            return;
+        }
+        let diff = (b.0 - a.0, b.1 - a.1);
+        match distance {
+            Distance::Ratio(num, den) => {
+                assert!(num > den && den > 0, "ratio has to be larger than one");
+                // b + diff * den / (num - den) is num / den times as far from a as from b
+                let (offset_0, offset_1) = (diff.0 * den, diff.1 * den);
+                if offset_0 % (num - den) != 0 || offset_1 % (num - den) != 0 {
+                    return;
+                }
+                let offset = (offset_0 / (num - den), offset_1 / (num - den));
+                let candidates = [
+                    Point(b.0 + offset.0, b.1 + offset.1),
+                    Point(a.0 - offset.0, a.1 - offset.1),
+                ];
+                antinodes.extend(candidates.into_iter().filter(|&pos| self.contains(pos)));
+            }
+            Distance::Harmonics(max) => {
+                // reduced by the gcd so no lattice point between the antennas is skipped
+                let divisor = gcd(diff.0, diff.1);
+                let step = (diff.0 / divisor, diff.1 / divisor);
+                let at = |k: i64| Point(a.0 + k * step.0, a.1 + k * step.1);
+                antinodes.extend((0..=divisor).map(at));
+                for (from, dir) in [(divisor, 1), (0, -1)] {
+                    let mut k = from + dir;
+                    while self.contains(at(k))
+                        && max.is_none_or(|max| (k - from).abs() <= max as i64)
+                    {
+                        antinodes.insert(at(k));
+                        k += dir;
                     }
                 }
-                row.push(Field::new(typ));
             }
-            map.push(row);
-        }
-        let x = map.len() - 1;
-        let y = map[0].len() - 1;
-        Map {
-            map: Vec2D(map),
-            antennas,
-            end: Position(x, y),
         }
     }
 
-    fn find_antinodes(&mut self, resonance: bool) {
-        for (_typ, antenna_positions) in self.antennas.iter() {
-            let mut other_antennas = antenna_positions.clone();
-            while let Some(pos) = other_antennas.pop() {
-                for &antenna_pos in other_antennas.iter() {
-                    let antinodes = if resonance {
-                        pos.antidotes_pos_resonant(antenna_pos, self.end)
-                    } else {
-                        pos.antidotes_pos(antenna_pos)
-                    };
-                    for antinode in antinodes.into_iter() {
-                        if let Some(field) = self.map.get_mut(antinode) {
-                            field.antinote = true;
-                        }
+    /// calls `f` for every pair of antennas the rule lets interfere, with their frequencies
+    fn for_each_pair(&self, rule: &Rule, mut f: impl FnMut((char, Point), (char, Point))) {
+        if !rule.cross_frequency {
+            for (&frequency, positions) in self.antennas.iter() {
+                for (idx, &a) in positions.iter().enumerate() {
+                    for &b in positions[idx + 1..].iter() {
+                        f((frequency, a), (frequency, b));
                     }
                 }
             }
+            return;
         }
+        let antennas: Vec<(char, Point)> = self
+            .antennas
+            .iter()
+            .flat_map(|(&frequency, positions)| positions.iter().map(move |&pos| (frequency, pos)))
+            .collect();
+        for (idx, &a) in antennas.iter().enumerate() {
+            for &b in antennas[idx + 1..].iter() {
+                f(a, b);
+            }
+        }
+    }
+
+    /// antinodes of all pairs involving a frequency, pairs of two frequencies count for both
+    pub fn antinodes_by_frequency(&self, rule: &Rule) -> HashMap<char, HashSet<Point>> {
+        let mut by_frequency: HashMap<char, HashSet<Point>> = HashMap::new();
+        self.for_each_pair(rule, |(frequency_a, a), (frequency_b, b)| {
+            if frequency_a == frequency_b {
+                let antinodes = by_frequency.entry(frequency_a).or_default();
+                self.pair_antinodes(a, b, rule.distance, antinodes);
+                return;
+            }
+            let mut antinodes = HashSet::new();
+            self.pair_antinodes(a, b, rule.distance, &mut antinodes);
+            by_frequency
+                .entry(frequency_b)
+                .or_default()
+                .extend(antinodes.iter());
+            by_frequency
+                .entry(frequency_a)
+                .or_default()
+                .extend(antinodes);
+        });
+        by_frequency
+    }
+
+    pub fn antinodes(&self, rule: &Rule) -> HashSet<Point> {
+        let mut antinodes = HashSet::new();
+        self.for_each_pair(rule, |(_, a), (_, b)| {
+            self.pair_antinodes(a, b, rule.distance, &mut antinodes)
+        });
+        antinodes
+    }
+
+    /// number of antinodes each frequency contributes to
+    pub fn breakdown(&self, rule: &Rule) -> BTreeMap<char, usize> {
+        self.antinodes_by_frequency(rule)
+            .into_iter()
+            .map(|(frequency, antinodes)| (frequency, antinodes.len()))
+            .collect()
     }
 }
 
-pub fn part_one(input: &str) -> usize {
-    let mut map = Map::parse(input);
-    map.find_antinodes(false);
-    //println!("{}", map.map);
-    map.map
-        .0
-        .iter()
-        .flatten()
-        .filter(|field| field.antinote)
-        .count()
+/// where a pair of antennas creates antinodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distance {
+    /// points `num / den` times as far from one antenna as from the other, outside of the pair
+    Ratio(i64, i64),
+    /// grid points on the line through the pair, including the antennas, at most the given
+    /// number of grid points beyond each antenna
+    Harmonics(Option<usize>),
 }
 
-pub fn part_two(input: &str) -> usize {
-    let mut map = Map::parse(input);
-    map.find_antinodes(true);
-    //println!("{}", map);
-    map.map
-        .0
-        .iter()
-        .flatten()
-        .filter(|field| field.antinote)
-        .count()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub distance: Distance,
+    /// antennas of different frequencies interfere as well
+    pub cross_frequency: bool,
 }
 
-impl Display for Field {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let c = match (&self.typ, self.antinote) {
-            (FieldType::Antenna(c), _) => *c,
-            (FieldType::Empty, true) => '#',
-            (FieldType::Empty, false) => '.',
-        };
-        f.write_char(c)
-    }
+impl Rule {
+    pub const TWICE_AS_FAR: Rule = Rule {
+        distance: Distance::Ratio(2, 1),
+        cross_frequency: false,
+    };
+
+    pub const RESONANT: Rule = Rule {
+        distance: Distance::Harmonics(None),
+        cross_frequency: false,
+    };
+}
+
+pub fn part_one(input: &str) -> usize {
+    let map = Map::parse(input);
+    map.antinodes(&Rule::TWICE_AS_FAR).len()
+}
+
+pub fn part_two(input: &str) -> usize {
+    let map = Map::parse(input);
+    map.antinodes(&Rule::RESONANT).len()
 }
 
 #[cfg(test)]
@@ -195,4 +221,100 @@ mod tests {
         assert_eq!(14, part_one(input));
         assert_eq!(34, part_two(input));
     }
+
+    #[test]
+    fn test_reduced_difference() {
+        let input = ".....
+.....
+..a..
+.....
+a....";
+        // the whole diagonal, including the point between the antennas
+        assert_eq!(5, part_two(input));
+        let antennas = HashMap::from([('a', vec![Point(0, 0), Point(2_000_000, 2_000_002)])]);
+        let map = Map::new(4_000_001, 5_000_000, antennas);
+        assert_eq!(
+            HashSet::from([Point(4_000_000, 4_000_004)]),
+            map.antinodes(&Rule::TWICE_AS_FAR)
+        );
+        let antinodes = map.antinodes(&Rule::RESONANT);
+        assert_eq!(5, antinodes.len());
+        assert!(antinodes.contains(&Point(1_000_000, 1_000_001)));
+    }
+
+    #[test]
+    fn test_duplicate_antennas() {
+        let antennas = HashMap::from([
+            ('a', vec![Point(1, 1), Point(1, 1), Point(2, 2)]),
+            ('b', vec![Point(2, 2)]),
+        ]);
+        let map = Map::new(5, 5, antennas);
+        // the stacked pair adds nothing, both copies pair up with the third antenna
+        assert_eq!(
+            HashSet::from([Point(0, 0), Point(3, 3)]),
+            map.antinodes(&Rule::TWICE_AS_FAR)
+        );
+        assert_eq!(5, map.antinodes(&Rule::RESONANT).len());
+        let cross = Rule {
+            distance: Distance::Harmonics(None),
+            cross_frequency: true,
+        };
+        assert_eq!(5, map.antinodes(&cross).len());
+    }
+
+    #[test]
+    fn test_rules() {
+        let input = "............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............";
+        let map = Map::parse(input);
+        assert_eq!(
+            BTreeMap::from([('0', 10), ('A', 5)]),
+            map.breakdown(&Rule::TWICE_AS_FAR)
+        );
+        let rule = |distance, cross_frequency| Rule {
+            distance,
+            cross_frequency,
+        };
+        // a ratio of three only hits the grid if the difference is even
+        let input = "a........
+.........
+..a......
+.........
+.........
+.........
+.........
+.........
+.........";
+        let map = Map::parse(input);
+        assert_eq!(
+            HashSet::from([Point(3, 3)]),
+            map.antinodes(&rule(Distance::Ratio(3, 1), false))
+        );
+        assert_eq!(
+            HashSet::from([Point(0, 0), Point(1, 1), Point(2, 2), Point(3, 3)]),
+            map.antinodes(&rule(Distance::Harmonics(Some(1)), false))
+        );
+        assert_eq!(
+            9,
+            map.antinodes(&rule(Distance::Harmonics(None), false)).len()
+        );
+        let map = Map::parse(".....\n.a...\n..b..\n.....\n.....");
+        assert!(map.antinodes(&Rule::TWICE_AS_FAR).is_empty());
+        let cross = rule(Distance::Ratio(2, 1), true);
+        assert_eq!(
+            HashSet::from([Point(0, 0), Point(3, 3)]),
+            map.antinodes(&cross)
+        );
+        assert_eq!(BTreeMap::from([('a', 2), ('b', 2)]), map.breakdown(&cross));
+    }
 }
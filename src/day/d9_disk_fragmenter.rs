@@ -1,173 +1,354 @@
-use std::fmt::Display;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::{Display, Write},
+};
 
-#[derive(Clone, Copy, Debug)]
-struct Block {
-    typ: BlockType,
-    len: usize,
+/// contiguous blocks of one file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Extent {
+    pub id: usize,
+    pub start: usize,
+    pub len: usize,
 }
 
-#[derive(Clone, Copy, Debug)]
-enum BlockType {
-    Free,
-    File(usize),
+impl Extent {
+    /// sum of position * id over all blocks of the extent
+    fn checksum(&self) -> usize {
+        self.id * (self.len * self.start + self.len * self.len.saturating_sub(1) / 2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub len: usize,
 }
 
-struct Disk(Vec<BlockType>);
+/// files on the disk, everything between them is free space
+pub struct Disk {
+    extents: Vec<Extent>,
+    /// files without blocks, kept apart so they neither split free space nor get moved
+    empty: Vec<Extent>,
+    size: usize,
+}
 
-struct BlockDisk {
-    disk: Vec<Block>,
-    files: usize,
+#[derive(Debug, PartialEq, Eq)]
+pub struct Fragmentation {
+    pub free_spans: usize,
+    pub largest_free_span: usize,
+    pub file_fragments: usize,
+}
+
+impl Display for Fragmentation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "free spans {:>6}, largest free span {:>6}, file fragments {:>6}",
+            self.free_spans, self.largest_free_span, self.file_fragments
+        )
+    }
 }
 
 impl Disk {
-    fn parse(input: &str) -> Self {
-        let mut disk = Vec::new();
-        let mut is_block = true;
-        let mut file_id = 0;
-        for c in input.chars() {
-            let number = c.to_digit(10).unwrap();
-            for _i in 0..number {
-                if is_block {
-                    disk.push(BlockType::File(file_id));
-                } else {
-                    disk.push(BlockType::Free);
+    /// sorts the extents by position and joins neighbouring blocks of the same file
+    fn new(extents: Vec<Extent>, size: usize) -> Self {
+        let (empty, mut extents): (Vec<Extent>, Vec<Extent>) =
+            extents.into_iter().partition(|extent| extent.len == 0);
+        extents.sort_by_key(|extent| extent.start);
+        let mut joined: Vec<Extent> = Vec::new();
+        for extent in extents {
+            match joined.last_mut() {
+                Some(last) if last.id == extent.id && last.start + last.len == extent.start => {
+                    last.len += extent.len
                 }
+                _ => joined.push(extent),
             }
-            if is_block {
-                file_id += 1;
+        }
+        Disk {
+            extents: joined,
+            empty,
+            size,
+        }
+    }
+
+    pub fn parse(input: &str) -> Self {
+        let mut extents = Vec::new();
+        let mut start = 0;
+        for (idx, c) in input.trim().chars().enumerate() {
+            let len = c.to_digit(10).unwrap() as usize;
+            if idx % 2 == 0 {
+                extents.push(Extent {
+                    id: idx / 2,
+                    start,
+                    len,
+                });
             }
-            is_block ^= true;
+            start += len;
         }
-        Disk(disk)
+        Disk::new(extents, start)
     }
 
-    fn defragment(&mut self) {
-        let mut idx_start = 0;
-        for (idx, block) in self.0.iter().enumerate() {
-            if let BlockType::Free = block {
-                idx_start = idx;
-                break;
+    /// parses the block view of the puzzle, e.g. `00...111...2`
+    pub fn parse_blocks(input: &str) -> Self {
+        let input = input.trim();
+        let mut extents = Vec::new();
+        for (start, c) in input.chars().enumerate() {
+            if c != '.' {
+                let id = c.to_digit(10).unwrap() as usize;
+                extents.push(Extent { id, start, len: 1 });
             }
         }
-        loop {
-            let last = self.0.pop().unwrap();
-            if let BlockType::Free = last {
-                continue;
+        Disk::new(extents, input.len())
+    }
+
+    /// dense digit format, `None` if files are fragmented, out of id order or a span is longer than 9
+    ///
+    /// files without blocks keep their place, ids missing altogether become empty files
+    pub fn to_disk_map(&self) -> Option<String> {
+        let mut extents: Vec<&Extent> = self.extents.iter().chain(self.empty.iter()).collect();
+        extents.sort_by_key(|extent| (extent.start, extent.id));
+        // alternating file and free lengths, always ending with a free one
+        let mut lens = Vec::new();
+        let mut pos = 0;
+        for extent in extents {
+            if 2 * extent.id < lens.len() {
+                return None;
             }
-            let mut found_free = false;
-            for idx in idx_start..self.0.len() {
-                let block = &mut self.0[idx];
-                if let BlockType::Free = block {
-                    *block = last;
-                    found_free = true;
-                    idx_start = idx + 1;
-                    break;
-                }
+            lens.resize(2 * extent.id, 0);
+            let gap = extent.start.checked_sub(pos)?;
+            match lens.last_mut() {
+                Some(free) => *free += gap,
+                None if gap > 0 => return None,
+                None => {}
+            }
+            lens.extend([extent.len, 0]);
+            pos = extent.start + extent.len;
+        }
+        if let Some(free) = lens.last_mut() {
+            *free += self.size - pos;
+            if *free == 0 {
+                lens.pop();
             }
-            if !found_free {
-                self.0.push(last);
-                break;
+        }
+        lens.into_iter()
+            .map(|len| char::from_digit(len as u32, 10))
+            .collect()
+    }
+
+    /// block view of the puzzle, `None` if a file id has more than one digit
+    pub fn to_blocks(&self) -> Option<String> {
+        let mut blocks = vec!['.'; self.size];
+        for extent in self.extents.iter() {
+            let c = char::from_digit(extent.id as u32, 10)?;
+            blocks[extent.start..extent.start + extent.len].fill(c);
+        }
+        Some(blocks.into_iter().collect())
+    }
+
+    pub fn free_spans(&self) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut start = 0;
+        let occupied = self
+            .extents
+            .iter()
+            .map(|extent| (extent.start, extent.start + extent.len))
+            .chain([(self.size, self.size)]);
+        for (extent_start, extent_end) in occupied {
+            if extent_start > start {
+                spans.push(Span {
+                    start,
+                    len: extent_start - start,
+                });
             }
+            start = extent_end;
+        }
+        spans
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.extents.iter().map(|extent| extent.checksum()).sum()
+    }
+
+    pub fn fragmentation(&self) -> Fragmentation {
+        let free_spans = self.free_spans();
+        Fragmentation {
+            free_spans: free_spans.len(),
+            largest_free_span: free_spans.iter().map(|span| span.len).max().unwrap_or(0),
+            file_fragments: self.extents.len(),
         }
     }
 }
 
-impl BlockDisk {
-    fn parse(input: &str) -> Self {
-        let mut disk = Vec::new();
-        let mut is_block = true;
-        let mut file_id = 0;
-        for c in input.chars() {
-            let len = c.to_digit(10).unwrap() as usize;
-            if len > 0 {
-                let typ = match is_block {
-                    true => BlockType::File(file_id),
-                    false => BlockType::Free,
+/// a way to move files to the front of the disk, space freed by a move is not reused
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+    fn compact(&self, disk: &Disk) -> Disk;
+}
+
+/// moves single blocks from the end of the disk into the leftmost free space
+pub struct BlockLevel;
+
+/// moves whole files into the leftmost free span which fits
+pub struct FirstFit;
+
+/// moves whole files into the smallest free span which fits
+pub struct BestFit;
+
+/// moves whole files into the largest free span which fits
+pub struct WorstFit;
+
+pub const STRATEGIES: [&dyn Strategy; 4] = [&BlockLevel, &FirstFit, &BestFit, &WorstFit];
+
+impl Strategy for BlockLevel {
+    fn name(&self) -> &'static str {
+        "block level"
+    }
+
+    fn compact(&self, disk: &Disk) -> Disk {
+        let mut extents = Vec::new();
+        let mut free = disk.free_spans().into_iter().peekable();
+        for file in disk.extents.iter().rev() {
+            let mut remaining = file.len;
+            while remaining > 0 {
+                let Some(span) = free.peek_mut().filter(|span| span.start < file.start) else {
+                    break;
                 };
-                let block = Block { typ, len };
-                disk.push(block);
-                if is_block {
-                    file_id += 1;
+                let len = span.len.min(remaining);
+                extents.push(Extent {
+                    id: file.id,
+                    start: span.start,
+                    len,
+                });
+                span.start += len;
+                span.len -= len;
+                remaining -= len;
+                if span.len == 0 {
+                    free.next();
                 }
             }
-            is_block ^= true;
-        }
-        BlockDisk {
-            disk,
-            files: file_id,
+            if remaining > 0 {
+                extents.push(Extent {
+                    len: remaining,
+                    ..*file
+                });
+            }
         }
+        extents.extend(disk.empty.iter().copied());
+        Disk::new(extents, disk.size)
     }
+}
+
+/// min-heap of start offsets per free span length
+type Spans = Vec<BinaryHeap<Reverse<usize>>>;
 
-    fn defragment(&mut self) {
-        for id in (0..self.files).rev() {
-            //println!("Move {id}");
-            //println!("{}", self);
-            let (idx_from, &block_from) = self
-                .disk
-                .iter()
-                .enumerate()
-                .find(|(_i, block)| matches!(block.typ, BlockType::File(i) if id == i))
-                .unwrap();
-            for idx in 0..idx_from {
-                let block_dest = &mut self.disk[idx];
-                match (block_dest.typ, block_dest.len) {
-                    (BlockType::Free, space) if space == block_from.len => {
-                        *block_dest = block_from;
-                        self.disk[idx_from].typ = BlockType::Free;
-                        break;
-                    }
-                    (BlockType::Free, space) if space > block_from.len => {
-                        *block_dest = block_from;
-                        self.disk[idx_from].typ = BlockType::Free;
-                        let free_block = Block {
-                            typ: BlockType::Free,
-                            len: space - block_from.len,
-                        };
-                        self.disk.insert(idx + 1, free_block);
-                        break;
-                    }
-                    (_, _) => {}
+fn fits_left(spans: &Spans, len: usize, file: &Extent) -> bool {
+    spans[len]
+        .peek()
+        .is_some_and(|&Reverse(start)| start < file.start)
+}
+
+/// moves files in order of decreasing id, `select` picks the length of the span to use
+fn compact_files(disk: &Disk, select: impl Fn(&Spans, &Extent) -> Option<usize>) -> Disk {
+    let free = disk.free_spans();
+    let max_len = free.iter().map(|span| span.len).max().unwrap_or(0);
+    let mut spans: Spans = vec![BinaryHeap::new(); max_len + 1];
+    for span in free.iter() {
+        spans[span.len].push(Reverse(span.start));
+    }
+    let mut files = disk.extents.clone();
+    files.sort_by_key(|file| Reverse((file.id, file.start)));
+    let mut extents = Vec::new();
+    for file in files {
+        match select(&spans, &file) {
+            Some(len) => {
+                let Reverse(start) = spans[len].pop().unwrap();
+                if len > file.len {
+                    spans[len - file.len].push(Reverse(start + file.len));
                 }
+                extents.push(Extent { start, ..file });
             }
+            None => extents.push(file),
         }
     }
+    extents.extend(disk.empty.iter().copied());
+    Disk::new(extents, disk.size)
 }
 
-impl Display for BlockDisk {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for block in self.disk.iter() {
-            f.write_fmt(format_args!("{:?}\n", block))?;
-        }
-        Ok(())
+impl Strategy for FirstFit {
+    fn name(&self) -> &'static str {
+        "first fit"
+    }
+
+    fn compact(&self, disk: &Disk) -> Disk {
+        compact_files(disk, |spans, file| {
+            (file.len..spans.len())
+                .filter(|&len| fits_left(spans, len, file))
+                .min_by_key(|&len| spans[len].peek().unwrap().0)
+        })
     }
 }
 
-pub fn part_one(input: &str) -> usize {
-    let mut disk = Disk::parse(input);
-    disk.defragment();
-    let mut checksum = 0;
-    for (pos, block) in disk.0.iter().enumerate() {
-        if let BlockType::File(id) = block {
-            checksum += pos * id;
-        }
+impl Strategy for BestFit {
+    fn name(&self) -> &'static str {
+        "best fit"
+    }
+
+    fn compact(&self, disk: &Disk) -> Disk {
+        compact_files(disk, |spans, file| {
+            (file.len..spans.len()).find(|&len| fits_left(spans, len, file))
+        })
     }
-    checksum
 }
 
-pub fn part_two(input: &str) -> usize {
-    let mut disk = BlockDisk::parse(input);
-    disk.defragment();
-    let mut checksum = 0;
-    let mut pos = 0;
-    for block in disk.disk.iter() {
-        for _ in 0..block.len {
-            if let BlockType::File(id) = block.typ {
-                checksum += pos * id;
-            }
-            pos += 1;
-        }
+impl Strategy for WorstFit {
+    fn name(&self) -> &'static str {
+        "worst fit"
+    }
+
+    fn compact(&self, disk: &Disk) -> Disk {
+        compact_files(disk, |spans, file| {
+            (file.len..spans.len())
+                .rev()
+                .find(|&len| fits_left(spans, len, file))
+        })
+    }
+}
+
+/// checksum and fragmentation of every strategy applied to the same disk map
+pub fn compare(input: &str) -> String {
+    let disk = Disk::parse(input);
+    let mut report = String::new();
+    writeln!(
+        report,
+        "{:<12} {:>16} {}",
+        "original",
+        "",
+        disk.fragmentation()
+    )
+    .unwrap();
+    for strategy in STRATEGIES {
+        let compacted = strategy.compact(&disk);
+        writeln!(
+            report,
+            "{:<12} {:>16} {}",
+            strategy.name(),
+            compacted.checksum(),
+            compacted.fragmentation()
+        )
+        .unwrap();
     }
-    checksum
+    report
+}
+
+pub fn part_one(input: &str) -> usize {
+    let disk = Disk::parse(input);
+    BlockLevel.compact(&disk).checksum()
+}
+
+pub fn part_two(input: &str) -> usize {
+    let disk = Disk::parse(input);
+    FirstFit.compact(&disk).checksum()
 }
 
 #[cfg(test)]
@@ -181,4 +362,80 @@ mod tests {
         assert_eq!(1928, part_one(input));
         assert_eq!(2858, part_two(input));
     }
+
+    #[test]
+    fn test_small() {
+        // 0..111....22222 -> 022111222......
+        assert_eq!(60, part_one("12345"));
+        assert_eq!(132, part_two("12345"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = "2333133121414131402";
+        let disk = Disk::parse(input);
+        assert_eq!(Some(input), disk.to_disk_map().as_deref());
+        let blocks = "00...111...2...333.44.5555.6666.777.888899";
+        assert_eq!(Some(blocks), disk.to_blocks().as_deref());
+        let disk = Disk::parse_blocks(blocks);
+        assert_eq!(Some(input), disk.to_disk_map().as_deref());
+        // an empty file keeps its place between the gaps around it
+        assert_eq!(Some("12031".into()), Disk::parse("12031").to_disk_map());
+        assert_eq!(Some("10021".into()), Disk::parse("10021").to_disk_map());
+        assert_eq!(Some("0201".into()), Disk::parse("0201").to_disk_map());
+        let disk = Disk::parse("12031");
+        assert_eq!(Some("02.....".into()), FirstFit.compact(&disk).to_blocks());
+        assert_eq!(1, disk.fragmentation().free_spans);
+    }
+
+    #[test]
+    fn test_compaction_states() {
+        let disk = Disk::parse("2333133121414131402");
+        let compacted = BlockLevel.compact(&disk);
+        assert_eq!(
+            Some("0099811188827773336446555566.............."),
+            compacted.to_blocks().as_deref()
+        );
+        assert_eq!(None, compacted.to_disk_map());
+        let compacted = FirstFit.compact(&disk);
+        assert_eq!(
+            Some("00992111777.44.333....5555.6666.....8888.."),
+            compacted.to_blocks().as_deref()
+        );
+        assert_eq!(
+            compacted.checksum(),
+            Disk::parse_blocks("00992111777.44.333....5555.6666.....8888..").checksum()
+        );
+        assert_eq!(
+            Some("201015"),
+            Disk::parse_blocks("0012.....").to_disk_map().as_deref()
+        );
+    }
+
+    #[test]
+    fn test_strategies() {
+        // 0...1..233
+        let disk = Disk::parse("1312102");
+        let fragmentation = |free_spans, largest_free_span| Fragmentation {
+            free_spans,
+            largest_free_span,
+            file_fragments: 4,
+        };
+        let expected = [
+            // 03321.....
+            ("block level", fragmentation(1, 5)),
+            // 03321.....
+            ("first fit", fragmentation(1, 5)),
+            // 021..33...
+            ("best fit", fragmentation(2, 3)),
+            // 0331.2....
+            ("worst fit", fragmentation(2, 4)),
+        ];
+        for (strategy, (name, fragmentation)) in STRATEGIES.into_iter().zip(expected) {
+            assert_eq!(name, strategy.name());
+            assert_eq!(fragmentation, strategy.compact(&disk).fragmentation());
+        }
+        assert_eq!(fragmentation(2, 3), disk.fragmentation());
+        assert_eq!(5, compare("1312102").lines().count());
+    }
 }
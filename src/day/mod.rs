@@ -5,6 +5,11 @@ pub mod d13_claw_contraption;
 pub mod d14_restroom_redoubt;
 pub mod d15_warehouse_woes;
 pub mod d1_id_check;
+pub mod d21_keypad_conundrum;
+pub mod d22_monkey_market;
+pub mod d23_lan_party;
+pub mod d24_crossed_wires;
+pub mod d25_code_chronicle;
 pub mod d2_reports;
 pub mod d3_mull_it_over;
 pub mod d4_ceres_search;
@@ -1,7 +1,4 @@
-mod day;
-mod util;
-
-use crate::day::*;
+use aoc2024::day::*;
 
 use std::time::Instant;
 use std::{fs::File, io::Read};
@@ -26,66 +23,80 @@ macro_rules! measure {
     }};
 }
 
-fn main() {
-    let input = read_file("input/15.txt");
-    //measure!("15.2", d15_warehouse_woes::part_two(&input));
-    measure!("15.1", d15_warehouse_woes::part_one(&input));
-
-    let input = read_file("input/14.txt");
-    measure!("14.2", d14_restroom_redoubt::part_two(&input));
-    measure!("14.1", d14_restroom_redoubt::part_one(&input));
-
-    let input = read_file("input/13.txt");
-    measure!("13.2", d13_claw_contraption::part_two(&input));
-    measure!("13.1", d13_claw_contraption::part_one(&input));
-
-    let input = read_file("input/12.txt");
-    measure!("12.2", d12_garden_groups::part_two(&input));
-    measure!("12.1", d12_garden_groups::part_one(&input));
-
-    let input = read_file("input/11.txt");
-    measure!("11.2", d11_plutonian_pebbles::part_two(&input));
-    measure!("11.1", d11_plutonian_pebbles::part_one(&input));
-
-    let input = read_file("input/10.txt");
-    measure!("10.2", d10_hoof_it::part_two(&input));
-    measure!("10.1", d10_hoof_it::part_one(&input));
-
-    let input = read_file("input/9.txt");
-    measure!("9.2", d9_disk_fragmenter::part_two(&input));
-    measure!("9.1", d9_disk_fragmenter::part_one(&input));
-
-    let input = read_file("input/8.txt");
-    measure!("8.2", d8_resonant_collinearity::part_two(&input));
-    measure!("8.1", d8_resonant_collinearity::part_one(&input));
+type Part = fn(&str) -> String;
 
-    let input = read_file("input/7.txt");
-    measure!("7.2", d7_bridge_repair::part_two(&input));
-    measure!("7.1", d7_bridge_repair::part_one(&input));
-
-    let input = read_file("input/6.txt");
-    measure!("6.2", d6_guard_gallivant::part_two(&input));
-    measure!("6.1", d6_guard_gallivant::part_one(&input));
-
-    let input = read_file("input/5.txt");
-    measure!("5.2", d5_print_queue::part_two(&input));
-    measure!("5.1", d5_print_queue::part_one(&input));
-
-    let input = read_file("input/4.txt");
-    measure!("4.2", d4_ceres_search::part_two(&input));
-    measure!("4.1", d4_ceres_search::part_one(&input));
+struct Day {
+    day: usize,
+    part_one: Part,
+    part_two: Option<Part>,
+}
 
-    let input = read_file("input/3.txt");
-    measure!("3.2", d3_mull_it_over::part_two(&input));
-    measure!("3.1", d3_mull_it_over::part_one(&input));
+macro_rules! day {
+    ($day:expr, $module:ident) => {
+        Day {
+            day: $day,
+            part_one: |input| $module::part_one(input).to_string(),
+            part_two: Some(|input| $module::part_two(input).to_string()),
+        }
+    };
+    ($day:expr, $module:ident, fallible) => {
+        Day {
+            day: $day,
+            part_one: |input| $module::part_one(input).to_string(),
+            part_two: Some(|input| match $module::part_two(input) {
+                Some(answer) => answer.to_string(),
+                None => "unsolved".to_string(),
+            }),
+        }
+    };
+    ($day:expr, $module:ident, part_one) => {
+        Day {
+            day: $day,
+            part_one: |input| $module::part_one(input).to_string(),
+            part_two: None,
+        }
+    };
+}
 
-    let input = read_file("input/2.txt");
-    measure!("2.2", d2_reports::part_two(&input));
-    measure!("2.1", d2_reports::part_one(&input));
+fn calendar() -> Vec<Day> {
+    vec![
+        day!(1, d1_id_check),
+        day!(2, d2_reports),
+        day!(3, d3_mull_it_over),
+        day!(4, d4_ceres_search),
+        day!(5, d5_print_queue),
+        day!(6, d6_guard_gallivant),
+        day!(7, d7_bridge_repair),
+        day!(8, d8_resonant_collinearity),
+        day!(9, d9_disk_fragmenter),
+        day!(10, d10_hoof_it),
+        day!(11, d11_plutonian_pebbles),
+        day!(12, d12_garden_groups),
+        day!(13, d13_claw_contraption),
+        day!(14, d14_restroom_redoubt),
+        day!(15, d15_warehouse_woes, part_one),
+        day!(21, d21_keypad_conundrum),
+        day!(22, d22_monkey_market),
+        day!(23, d23_lan_party),
+        day!(24, d24_crossed_wires, fallible),
+        day!(25, d25_code_chronicle, part_one),
+    ]
+}
 
-    let input = read_file("input/1.txt");
-    measure!("1.2", d1_id_check::part_two(&input));
-    measure!("1.1", d1_id_check::part_one(&input));
+fn main() {
+    let calendar = calendar();
+    for day in (1..=25).rev() {
+        let Some(solution) = calendar.iter().find(|solution| solution.day == day) else {
+            println!("Day {:>4} {:>11}", day, "unsolved");
+            continue;
+        };
+        let input = read_file(&format!("input/{}.txt", day));
+        match solution.part_two {
+            Some(part_two) => measure!(format!("{}.2", day), part_two(&input)),
+            None => println!("Day {:>4} {:>11}", format!("{}.2", day), "-"),
+        }
+        measure!(format!("{}.1", day), (solution.part_one)(&input));
+    }
 }
 
 fn read_file(path: &str) -> String {
@@ -0,0 +1,60 @@
+use std::fmt::{Display, Write};
+
+/// minimal writer for graphviz DOT files
+pub struct Dot {
+    directed: bool,
+    body: String,
+}
+
+impl Dot {
+    pub fn graph() -> Self {
+        Dot {
+            directed: false,
+            body: String::new(),
+        }
+    }
+
+    pub fn digraph() -> Self {
+        Dot {
+            directed: true,
+            body: String::new(),
+        }
+    }
+
+    pub fn node(&mut self, id: &str, attributes: &[(&str, &str)]) {
+        writeln!(self.body, "  \"{}\"{};", id, Self::attributes(attributes)).unwrap();
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, attributes: &[(&str, &str)]) {
+        let arrow = if self.directed { "->" } else { "--" };
+        writeln!(
+            self.body,
+            "  \"{}\" {} \"{}\"{};",
+            from,
+            arrow,
+            to,
+            Self::attributes(attributes)
+        )
+        .unwrap();
+    }
+
+    fn attributes(attributes: &[(&str, &str)]) -> String {
+        if attributes.is_empty() {
+            return String::new();
+        }
+        let attributes: Vec<String> = attributes
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, value))
+            .collect();
+        format!(" [{}]", attributes.join(", "))
+    }
+}
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.directed { "digraph" } else { "graph" };
+        writeln!(f, "{} {{", kind)?;
+        f.write_str(&self.body)?;
+        f.write_char('}')
+    }
+}
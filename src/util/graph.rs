@@ -0,0 +1,105 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// undirected graph, nodes are referenced by the id they got when first inserted
+pub struct Graph<N> {
+    nodes: Vec<N>,
+    ids: HashMap<N, usize>,
+    edges: Vec<HashSet<usize>>,
+}
+
+impl<N> Default for Graph<N> {
+    fn default() -> Self {
+        Graph {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl<N: Clone + Eq + Hash> Graph<N> {
+    fn insert(&mut self, node: N) -> usize {
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        self.edges.push(HashSet::new());
+        id
+    }
+
+    pub fn add_edge(&mut self, a: N, b: N) {
+        let a = self.insert(a);
+        let b = self.insert(b);
+        self.edges[a].insert(b);
+        self.edges[b].insert(a);
+    }
+}
+
+impl<N> Graph<N> {
+    pub fn node(&self, id: usize) -> &N {
+        &self.nodes[id]
+    }
+
+    /// every triangle once, with ascending ids
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = Vec::new();
+        for (a, neighbours) in self.edges.iter().enumerate() {
+            for &b in neighbours.iter().filter(|&&b| b > a) {
+                for &c in self.edges[b].iter().filter(|&&c| c > b) {
+                    if neighbours.contains(&c) {
+                        triangles.push([a, b, c]);
+                    }
+                }
+            }
+        }
+        triangles
+    }
+
+    pub fn max_clique(&self) -> Vec<usize> {
+        let mut best = Vec::new();
+        let candidates = (0..self.nodes.len()).collect();
+        self.bron_kerbosch(&mut Vec::new(), candidates, HashSet::new(), &mut best);
+        best
+    }
+
+    /// Bron–Kerbosch with pivoting, `clique` is extended by `candidates`, `excluded` were already tried
+    fn bron_kerbosch(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: HashSet<usize>,
+        mut excluded: HashSet<usize>,
+        best: &mut Vec<usize>,
+    ) {
+        let pivot = candidates
+            .union(&excluded)
+            .max_by_key(|&&node| candidates.intersection(&self.edges[node]).count());
+        let Some(&pivot) = pivot else {
+            if clique.len() > best.len() {
+                *best = clique.clone();
+            }
+            return;
+        };
+        let branches: Vec<usize> = candidates
+            .difference(&self.edges[pivot])
+            .copied()
+            .collect();
+        for node in branches {
+            let neighbours = &self.edges[node];
+            clique.push(node);
+            self.bron_kerbosch(
+                clique,
+                candidates.intersection(neighbours).copied().collect(),
+                excluded.intersection(neighbours).copied().collect(),
+                best,
+            );
+            clique.pop();
+            candidates.remove(&node);
+            excluded.insert(node);
+        }
+    }
+}
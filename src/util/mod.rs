@@ -1,2 +1,8 @@
+pub mod dot;
+pub mod graph;
+pub mod parallel;
 pub mod position;
+#[cfg(test)]
+pub mod rng;
+pub mod union_find;
 pub mod vec2d;
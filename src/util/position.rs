@@ -36,3 +36,17 @@ impl Direction {
         [Up, Left, Right, Down].into_iter()
     }
 }
+
+impl TryFrom<char> for Direction {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '^' => Ok(Up),
+            'v' => Ok(Down),
+            '>' => Ok(Right),
+            '<' => Ok(Left),
+            c => Err(c),
+        }
+    }
+}